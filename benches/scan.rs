@@ -0,0 +1,83 @@
+//! Rough timings of a vault scan: `cargo bench`.
+//!
+//! Builds a throwaway vault of generated notes and reports the best of a few
+//! runs for each configuration.
+
+use obsidian_tasks::{collect_tasks_from, parse_task_files, task_files, ScanOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const NOTES: usize = 2000;
+const RUNS: usize = 5;
+
+fn main() {
+    let vault = std::env::temp_dir().join(format!("obsidian-tasks-bench-{}", std::process::id()));
+    write_vault(&vault);
+    let paths = [vault.clone()];
+
+    let plain = ScanOptions::default();
+    report("uncached", best_of(|| scan(&paths, &plain)));
+
+    let files = task_files(&vault, &plain);
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get().max(2));
+    report("parse, 1 thread", best_of(|| parse(&files, 1)));
+    report(
+        &format!("parse, {} threads", threads),
+        best_of(|| parse(&files, threads)),
+    );
+
+    let cache = vault.with_extension("cache.json");
+    let _ = fs::remove_file(&cache);
+    let cached = ScanOptions {
+        cache: Some(cache.clone()),
+        ..ScanOptions::default()
+    };
+    scan(&paths, &cached);
+    report("cached (warm)", best_of(|| scan(&paths, &cached)));
+
+    let _ = fs::remove_file(&cache);
+    let _ = fs::remove_dir_all(&vault);
+}
+
+fn write_vault(vault: &Path) {
+    let _ = fs::remove_dir_all(vault);
+    fs::create_dir_all(vault).expect("create bench vault");
+    for i in 0..NOTES {
+        let note = format!(
+            "---\ntitle: Task {i}\nstatus: {}\npriority: high\ndateCreated: 2026-01-30T08:18:47.998-05:00\n\
+             tags:\n  - task\nprojects:\n  - \"[[Project {}]]\"\ndue: 2026-10-{:02}\n---\n{}\n",
+            if i % 3 == 0 { "done" } else { "open" },
+            i % 10,
+            i % 28 + 1,
+            "Some body text for the note.\n".repeat(20),
+        );
+        fs::write(vault.join(format!("task-{i}.md")), note).expect("write bench note");
+    }
+}
+
+fn scan(paths: &[PathBuf], options: &ScanOptions) {
+    let tasks = collect_tasks_from(paths, options).expect("scan bench vault");
+    assert_eq!(tasks.len(), NOTES);
+}
+
+fn parse(files: &[PathBuf], threads: usize) {
+    let tasks = parse_task_files(files, threads, None);
+    assert!(tasks.iter().all(|t| t.is_ok()));
+    assert_eq!(tasks.len(), NOTES);
+}
+
+fn best_of(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .expect("at least one run")
+}
+
+fn report(name: &str, time: Duration) {
+    println!("{:<24} {:>8.2} ms", name, time.as_secs_f64() * 1000.0);
+}
@@ -0,0 +1,180 @@
+//! On-disk cache of parsed tasks, so repeated runs only re-parse edited notes.
+//!
+//! Entries are keyed by file path and reused only while the file's
+//! modification time and size are unchanged. Tasks are stored with their
+//! values as written (timestamps keep their UTC offset), so a cache filled
+//! under one `--timezone` is just as valid under another.
+
+use crate::Task;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bumped whenever the entry layout or `Task` fields change, discarding old caches
+const CACHE_VERSION: u32 = 7;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    modified: SystemTime,
+    size: u64,
+    /// Stored separately since `Task` only serializes what appears in output
+    filename: String,
+    body: String,
+    task: Task,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<PathBuf, Entry>,
+}
+
+/// Parsed tasks keyed by path, loaded from and saved to a JSON file
+pub struct TaskCache {
+    path: PathBuf,
+    entries: HashMap<PathBuf, Entry>,
+    /// Paths looked up during this run; anything else is pruned on save
+    seen: Vec<PathBuf>,
+    dirty: bool,
+}
+
+/// The modification time and size used to tell whether a file changed
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl TaskCache {
+    /// Load the cache at `path`. A missing, unreadable or outdated cache file
+    /// just starts out empty.
+    pub fn load(path: &Path) -> TaskCache {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        TaskCache {
+            path: path.to_path_buf(),
+            entries,
+            seen: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    /// The cached task for `path`, if the file hasn't changed since it was stored
+    pub fn get(&mut self, path: &Path) -> Option<Task> {
+        self.seen.push(path.to_path_buf());
+        let (modified, size) = fingerprint(path)?;
+        let entry = self.entries.get(path)?;
+        if entry.modified != modified || entry.size != size {
+            return None;
+        }
+        let mut task = entry.task.clone();
+        task.filename = entry.filename.clone();
+        task.body = entry.body.clone();
+        task.path = path.to_path_buf();
+        task.modified = Some(modified);
+        Some(task)
+    }
+
+    /// Store a freshly parsed task for `path`
+    pub fn insert(&mut self, path: &Path, task: &Task) {
+        let Some((modified, size)) = fingerprint(path) else {
+            return;
+        };
+        self.entries.insert(
+            path.to_path_buf(),
+            Entry {
+                modified,
+                size,
+                filename: task.filename.clone(),
+                body: task.body.clone(),
+                task: task.clone(),
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Write the cache back, dropping entries for files no longer scanned.
+    /// Does nothing when nothing changed.
+    pub fn save(mut self) -> Result<()> {
+        let before = self.entries.len();
+        let seen: std::collections::HashSet<_> = self.seen.drain(..).collect();
+        self.entries.retain(|path, _| seen.contains(path));
+        if !self.dirty && self.entries.len() == before {
+            return Ok(());
+        }
+
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries: self.entries,
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so a concurrent run never reads half a cache
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(&file)?)
+            .with_context(|| format!("Failed to write cache: {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write cache: {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskCache;
+    use crate::parse_task_file;
+    use crate::testutil::TempDir;
+
+    fn cache_round_trip(dir: &TempDir, note: &std::path::Path) -> Option<crate::Task> {
+        let cache_path = dir.path().join("cache.json");
+        let mut cache = TaskCache::load(&cache_path);
+        if cache.get(note).is_none() {
+            cache.insert(note, &parse_task_file(note).unwrap());
+        }
+        cache.save().unwrap();
+        TaskCache::load(&cache_path).get(note)
+    }
+
+    #[test]
+    fn editing_a_note_busts_its_entry() {
+        let dir = TempDir::new();
+        let note = dir.write("a.md", "---\nstatus: open\n---\nbody\n");
+        let cached = cache_round_trip(&dir, &note).expect("cached after first run");
+        assert_eq!(cached.status, "open");
+        assert_eq!(cached.body, "body");
+        assert_eq!(cached.filename, "a");
+
+        dir.write("a.md", "---\nstatus: done\n---\nbody, longer now\n");
+        let cache_path = dir.path().join("cache.json");
+        assert!(TaskCache::load(&cache_path).get(&note).is_none());
+    }
+
+    #[test]
+    fn timestamps_are_cached_as_written() {
+        let dir = TempDir::new();
+        let note = dir.write(
+            "a.md",
+            "---\nstatus: open\ndue: 2026-01-16T08:30:00+09:00\n---\n",
+        );
+        let cached = cache_round_trip(&dir, &note).unwrap();
+        assert_eq!(cached.due.unwrap().to_string(), "2026-01-16T08:30:00+09:00");
+        assert_eq!(cached.due, parse_task_file(&note).unwrap().due);
+    }
+
+    #[test]
+    fn outdated_or_corrupt_caches_start_empty() {
+        let dir = TempDir::new();
+        let note = dir.write("a.md", "---\nstatus: open\n---\n");
+        let cache_path = dir.write("cache.json", "{\"version\": 0, \"entries\": {}}");
+        assert!(TaskCache::load(&cache_path).get(&note).is_none());
+        dir.write("cache.json", "not json");
+        assert!(TaskCache::load(&cache_path).get(&note).is_none());
+    }
+}
@@ -0,0 +1,199 @@
+//! A month grid of due tasks for the terminal.
+
+use crate::Task;
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, Weekday};
+use std::collections::BTreeMap;
+
+/// How many open tasks are due on a day, and whether any of them is overdue
+#[derive(Debug, Default, Clone, Copy)]
+struct Day {
+    due: usize,
+    overdue: bool,
+}
+
+/// Render the month containing `now` as a grid, one week per row starting on
+/// `week_start`. Each day shows how many open tasks are due on it; today is
+/// bracketed and days holding tasks overdue at `now` get a `!`, and with
+/// `color` they are shown in reverse video and red as well.
+pub fn render_month(
+    tasks: &[&Task],
+    now: NaiveDateTime,
+    week_start: Weekday,
+    color: bool,
+) -> String {
+    let today = now.date();
+    let first = today.with_day(1).expect("every month has a first day");
+    let next_month = first + chrono::Months::new(1);
+
+    let mut days: BTreeMap<NaiveDate, Day> = BTreeMap::new();
+    for task in tasks.iter().filter(|t| t.is_open()) {
+        let Some(due) = task.due.map(|d| d.date()) else {
+            continue;
+        };
+        if due < first || due >= next_month {
+            continue;
+        }
+        let day = days.entry(due).or_default();
+        day.due += 1;
+        day.overdue |= task.is_overdue(now);
+    }
+
+    // Every cell is as wide as the widest one, so the columns stay aligned
+    // with three-digit counts and with today's extra overdue marker
+    let width = days
+        .iter()
+        .map(|(date, day)| cell_text(*date, *day, today).len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+
+    let header: String = std::iter::successors(Some(week_start), |w| Some(w.succ()))
+        .take(7)
+        .map(|weekday| format!("{:<width$}", format!(" {}", &weekday.to_string()[..2])))
+        .collect();
+    let mut lines = vec![
+        format!("{:^1$}", first.format("%B %Y").to_string(), 7 * width)
+            .trim_end()
+            .to_string(),
+        header.trim_end().to_string(),
+    ];
+
+    let mut day = first - Days::new(first.weekday().days_since(week_start) as u64);
+    while day < next_month {
+        let mut row = String::new();
+        for _ in 0..7 {
+            if day.month() == first.month() {
+                row.push_str(&cell(
+                    day,
+                    days.get(&day).copied().unwrap_or_default(),
+                    today,
+                    width,
+                    color,
+                ));
+            } else {
+                row.push_str(&" ".repeat(width));
+            }
+            day = day + Days::new(1);
+        }
+        lines.push(row.trim_end().to_string());
+    }
+
+    let total: usize = days.values().map(|d| d.due).sum();
+    lines.push(String::new());
+    lines.push(format!(
+        "{} open tasks due this month; (n) tasks due that day, ! overdue, [ ] today",
+        total
+    ));
+    lines.join("\n")
+}
+
+/// One day of the grid, padded to `width` columns
+fn cell(date: NaiveDate, day: Day, today: NaiveDate, width: usize, color: bool) -> String {
+    let text = format!("{:<width$}", cell_text(date, day, today));
+    let style = match (date == today, day.overdue) {
+        (true, true) => Some("\x1b[7;31m"),
+        (true, false) => Some("\x1b[7m"),
+        (false, true) => Some("\x1b[31m"),
+        (false, false) => None,
+    };
+    match style {
+        Some(style) if color => format!("{}{}\x1b[0m", style, text),
+        _ => text,
+    }
+}
+
+/// The day number and count of a cell, bracketed for today and followed by
+/// `!` when overdue, e.g. ` 3(2)!` or `[14(1)]!`
+fn cell_text(date: NaiveDate, day: Day, today: NaiveDate) -> String {
+    let count = if day.due > 0 {
+        format!("({})", day.due)
+    } else {
+        String::new()
+    };
+    let overdue = if day.overdue { "!" } else { "" };
+    if date == today {
+        format!("[{:>2}{}]{}", date.day(), count, overdue)
+    } else {
+        format!(" {:>2}{}{}", date.day(), count, overdue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_task_str;
+
+    fn due_on(date: &str, count: usize) -> Vec<Task> {
+        (0..count)
+            .map(|_| {
+                parse_task_str(&format!("---\nstatus: open\ndue: {}\n---\n", date), "t").unwrap()
+            })
+            .collect()
+    }
+
+    fn render(tasks: &[Task], today: &str) -> Vec<String> {
+        let tasks: Vec<&Task> = tasks.iter().collect();
+        let today: NaiveDate = today.parse().unwrap();
+        render_month(
+            &tasks,
+            today.and_hms_opt(12, 0, 0).unwrap(),
+            Weekday::Mon,
+            false,
+        )
+        .lines()
+        .map(str::to_string)
+        .collect()
+    }
+
+    /// The column of the grid each day of January 2000 (which starts on a
+    /// Saturday) begins at, given the cell `width`
+    fn assert_aligned(lines: &[String], width: usize) {
+        for day in 1..=31u32 {
+            let index = day as usize + 4;
+            let (row, column) = (2 + index / 7, index % 7);
+            let start = column * width;
+            let text = &lines[row][start..];
+            assert!(
+                text[1..].starts_with(&format!("{:>2}", day)),
+                "day {} not at column {} in {:?}",
+                day,
+                start,
+                lines[row]
+            );
+        }
+    }
+
+    #[test]
+    fn today_keeps_its_overdue_marker() {
+        let tasks = due_on("2000-01-12T09:00", 2);
+        let lines = render(&tasks, "2000-01-12");
+        assert!(lines.iter().any(|l| l.contains("[12(2)]!")), "{:#?}", lines);
+        assert_aligned(&lines, 8);
+
+        let lines = render(&tasks, "2000-01-13");
+        assert!(
+            lines.iter().any(|l| l.contains(" 12(2)![13]")),
+            "{:#?}",
+            lines
+        );
+        assert_aligned(&lines, 7);
+    }
+
+    #[test]
+    fn large_counts_keep_the_columns_aligned() {
+        let mut tasks = due_on("2000-01-05", 120);
+        tasks.extend(due_on("2000-01-20", 3));
+        let lines = render(&tasks, "2000-01-31");
+        assert!(
+            lines.iter().any(|l| l.contains("  5(120)! ")),
+            "{:#?}",
+            lines
+        );
+        assert_aligned(&lines, 9);
+        assert!(lines[1].starts_with(" Mo       Tu"));
+        assert_eq!(
+            lines.last().unwrap(),
+            "123 open tasks due this month; (n) tasks due that day, ! overdue, [ ] today"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! The current date and time, in the system zone or a configured `--timezone`.
+//!
+//! Date filters don't read the clock themselves: callers pass in the moment
+//! they are evaluated at, which is [`now`] unless it was moved with `--as-of`.
+
+use crate::tz::Tz;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Use `tz` instead of the system local zone for the rest of the process.
+/// Only the first call has any effect.
+pub fn set_timezone(tz: Tz) {
+    let _ = TIMEZONE.set(tz);
+}
+
+/// The zone set with [`set_timezone`], if any
+pub fn timezone() -> Option<&'static Tz> {
+    TIMEZONE.get()
+}
+
+/// Wall-clock time of a UTC instant in `tz`, or the system zone when `None`
+pub fn to_local(utc: DateTime<Utc>, tz: Option<&Tz>) -> NaiveDateTime {
+    match tz {
+        Some(tz) => tz.from_utc(utc.naive_utc()),
+        None => utc.with_timezone(&Local).naive_local(),
+    }
+}
+
+/// The current wall-clock time in `tz`, or the system zone when `None`
+pub fn now(tz: Option<&Tz>) -> NaiveDateTime {
+    to_local(Utc::now(), tz)
+}
+
+/// The current time in `tz` (or the system zone) with its UTC offset, for
+/// timestamps written into notes
+pub fn now_with_offset(tz: Option<&Tz>) -> DateTime<FixedOffset> {
+    let utc = Utc::now();
+    let offset = match tz {
+        Some(tz) => tz.offset_at(utc.timestamp()),
+        None => utc.with_timezone(&Local).offset().local_minus_utc(),
+    };
+    let offset = FixedOffset::east_opt(offset).unwrap_or(FixedOffset::east_opt(0).expect("UTC"));
+    utc.with_timezone(&offset)
+}
+
+/// Today's date in `tz`, or the system zone when `None`
+pub fn today(tz: Option<&Tz>) -> NaiveDate {
+    now(tz).date()
+}
+
+/// A threshold given either as a date (`2024-05-01`, from local midnight) or
+/// as a span back from now (`30m`, `12h`, `3d`, `2w`)
+#[derive(Debug, Clone, Copy)]
+pub enum Since {
+    Date(NaiveDate),
+    Ago(Duration),
+}
+
+impl Since {
+    /// The threshold as wall-clock time, with spans counted back from `now`
+    pub fn resolve(self, now: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Since::Date(date) => date.and_time(NaiveTime::MIN),
+            Since::Ago(span) => now - span,
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Since> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Since::Date(date));
+        }
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (amount, unit) = s.split_at(split);
+        let Ok(amount) = amount.parse::<i64>() else {
+            bail!(
+                "expected a date like 2024-05-01 or a duration like 3d, got `{}`",
+                s
+            );
+        };
+        let span = match unit {
+            "m" => Duration::try_minutes(amount),
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            _ => bail!("unknown duration unit `{}`; use m, h, d or w", unit),
+        };
+        match span {
+            Some(span) => Ok(Since::Ago(span)),
+            None => bail!("duration `{}` is too large", s),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Defaults loaded from a `crustsidian.toml` config file.
+
+use crate::render::OutputFormat;
+use crate::tz::Tz;
+use crate::waybar::WaybarTier;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const CONFIG_FILE_NAME: &str = "crustsidian.toml";
+
+/// Settings read from the config file; every field is optional and CLI flags win
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Default vault TaskNotes folder, used when `--path` is not given
+    pub path: Option<PathBuf>,
+    /// Default output format, used when `--format` is not given
+    pub format: Option<OutputFormat>,
+    /// IANA timezone used for "today", used when `--timezone` is not given
+    #[serde(deserialize_with = "deserialize_timezone")]
+    pub timezone: Option<Tz>,
+    /// Exclude patterns applied in addition to any `--exclude` flags
+    pub exclude: Vec<String>,
+    /// Extra statuses counted as done, on top of `done`, `completed` and `x`
+    pub done_statuses: Vec<String>,
+    /// Extra statuses counted as cancelled, on top of `cancelled` and `canceled`
+    pub cancelled_statuses: Vec<String>,
+    /// Priority levels from lowest to highest, replacing `low`, `medium`, `high`
+    pub priorities: Vec<String>,
+    /// Checkbox symbols by status, over the defaults `[ ]`, `[/]`, `[x]`, `[-]`
+    pub status_symbols: HashMap<String, String>,
+    /// Settings for `count --waybar`, under `[waybar]`
+    pub waybar: WaybarConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WaybarConfig {
+    /// Classes chosen by the count, checked in order, e.g.
+    /// `tiers = [{ max = 0, class = "ok" }, { class = "crit" }]`
+    pub tiers: Vec<WaybarTier>,
+}
+
+fn deserialize_timezone<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Tz>, D::Error> {
+    let name: Option<String> = Option::deserialize(deserializer)?;
+    name.map(|name| name.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Places a config file is looked for, in priority order: the current
+/// directory, then `$XDG_CONFIG_HOME` (falling back to `~/.config`).
+pub fn config_search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(CONFIG_FILE_NAME)];
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    if let Some(dir) = config_home {
+        paths.push(dir.join(CONFIG_FILE_NAME));
+    }
+    paths
+}
+
+/// Load the first config file found in [`config_search_paths`], or defaults if none exists
+pub fn load_config() -> Result<Config> {
+    match config_search_paths().into_iter().find(|p| p.is_file()) {
+        Some(path) => load_config_from(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+pub fn load_config_from(path: &std::path::Path) -> Result<Config> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config: {}", path.display()))?;
+    let mut config: Config = crate::toml::from_str_as(&content)
+        .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+    config.path = config.path.map(expand_home);
+    Ok(config)
+}
+
+/// Expand a leading `~` to the user's home directory
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_files_read_every_setting() {
+        let dir = crate::testutil::TempDir::new();
+        let path = dir.write(
+            CONFIG_FILE_NAME,
+            "path = \"/vault/Tasks\"\n\
+             format = \"csv\"\n\
+             exclude = [\"Templates\"]\n\
+             priorities = [\"low\", \"high\", \"urgent\"]\n\
+             [status_symbols]\n\
+             waiting = \"[?]\"\n\
+             [waybar]\n\
+             tiers = [{ max = 0, class = \"ok\" }, { class = \"crit\" }]\n",
+        );
+        let config = load_config_from(&path).unwrap();
+        assert_eq!(config.path, Some(PathBuf::from("/vault/Tasks")));
+        assert!(matches!(config.format, Some(OutputFormat::Csv)));
+        assert_eq!(config.exclude, ["Templates"]);
+        assert_eq!(config.priorities, ["low", "high", "urgent"]);
+        assert_eq!(config.status_symbols["waiting"], "[?]");
+        assert_eq!(config.waybar.tiers.len(), 2);
+        assert!(config.timezone.is_none() && config.done_statuses.is_empty());
+    }
+
+    #[test]
+    fn bad_config_files_name_the_file() {
+        let dir = crate::testutil::TempDir::new();
+        let path = dir.write(CONFIG_FILE_NAME, "format = \"yaml\"\n");
+        let err = format!("{:#}", load_config_from(&path).unwrap_err());
+        assert!(err.starts_with("Failed to parse config: "), "{}", err);
+        assert!(load_config_from(&dir.path().join("missing.toml")).is_err());
+    }
+
+    #[test]
+    fn a_leading_tilde_is_the_home_folder() {
+        let Some(home) = env::var_os("HOME") else {
+            return;
+        };
+        assert_eq!(
+            expand_home(PathBuf::from("~/vault")),
+            PathBuf::from(home).join("vault")
+        );
+        assert_eq!(expand_home(PathBuf::from("/a/~")), PathBuf::from("/a/~"));
+    }
+}
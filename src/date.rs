@@ -0,0 +1,301 @@
+//! Frontmatter dates that may or may not carry a time of day.
+
+use crate::clock;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A date such as `2024-06-01`, a date with a time such as `2024-06-01T14:30`,
+/// or a timestamp with a UTC offset such as `2024-06-01T14:30:00-05:00`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskDate {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    /// Kept with the offset it was written with, so it prints back unchanged;
+    /// comparisons use its wall-clock time in the configured zone
+    Offset(DateTime<FixedOffset>),
+}
+
+impl TaskDate {
+    /// The calendar day, ignoring any time of day
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            TaskDate::Date(d) => *d,
+            TaskDate::DateTime(_) | TaskDate::Offset(_) => self.wall_clock().date(),
+        }
+    }
+
+    pub fn time(&self) -> Option<NaiveTime> {
+        match self {
+            TaskDate::Date(_) => None,
+            TaskDate::DateTime(_) | TaskDate::Offset(_) => Some(self.wall_clock().time()),
+        }
+    }
+
+    /// Wall-clock time in the configured zone, with a date-only value at midnight
+    fn wall_clock(&self) -> NaiveDateTime {
+        match self {
+            TaskDate::Date(d) => d.and_time(NaiveTime::MIN),
+            TaskDate::DateTime(dt) => *dt,
+            TaskDate::Offset(dt) => clock::to_local(dt.with_timezone(&Utc), clock::timezone()),
+        }
+    }
+
+    /// Whether this moment has passed at `now`. A date-only value lasts the whole
+    /// day, so it only counts as passed once that day is over.
+    pub fn is_before(&self, now: NaiveDateTime) -> bool {
+        match self {
+            TaskDate::Date(d) => *d < now.date(),
+            TaskDate::DateTime(_) | TaskDate::Offset(_) => self.wall_clock() < now,
+        }
+    }
+}
+
+impl Ord for TaskDate {
+    /// Chronological, with a date-only value sorting after any timed value on the same day
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.date(), self.time().is_none(), self.time()).cmp(&(
+            other.date(),
+            other.time().is_none(),
+            other.time(),
+        ))
+    }
+}
+
+impl PartialOrd for TaskDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<NaiveDate> for TaskDate {
+    fn from(date: NaiveDate) -> Self {
+        TaskDate::Date(date)
+    }
+}
+
+impl FromStr for TaskDate {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(TaskDate::Date(date));
+        }
+        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+            return Ok(TaskDate::Offset(dt));
+        }
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%.f",
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%d %H:%M:%S%.f",
+        ] {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+                return Ok(TaskDate::DateTime(dt));
+            }
+        }
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").map(TaskDate::DateTime)
+    }
+}
+
+impl fmt::Display for TaskDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskDate::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            // Fractional seconds only appear when present, e.g. in `dateCreated`
+            TaskDate::DateTime(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f")),
+            // A `Z` suffix comes back as `+00:00`
+            TaskDate::Offset(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f%:z")),
+        }
+    }
+}
+
+impl Serialize for TaskDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|e| serde::de::Error::custom(format!("invalid date `{}`: {}", s, e)))
+    }
+}
+
+/// Deserialize an optional date for a frontmatter field, treating an empty or
+/// whitespace-only string the same as a missing value. Quoted and bare dates
+/// both reach this as strings.
+pub(crate) fn deserialize_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let Some(s) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let trimmed = s.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    trimmed
+        .parse()
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("invalid date `{}`: {}", s, e)))
+}
+
+/// Like [`deserialize_optional`], but a value that isn't a recognizable date
+/// is read as missing instead of rejecting the whole note. Used for
+/// `dateCreated`, which other tools fill in and nothing here depends on.
+pub(crate) fn deserialize_lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    let value = Option::<serde_yaml::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_yaml::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaskDate;
+    use chrono::NaiveDate;
+    use serde::Deserialize;
+
+    fn parse(s: &str) -> TaskDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn offsets_print_back_as_written() {
+        for s in [
+            "2026-01-30T08:18:47.998-05:00",
+            "2026-01-30T08:18:47+09:30",
+            "2026-01-30",
+            "2026-01-30T08:18:00",
+            "2026-01-30T08:18:47.998",
+        ] {
+            assert_eq!(parse(s).to_string(), s);
+        }
+        assert_eq!(
+            parse("2026-01-30T08:18:47Z").to_string(),
+            "2026-01-30T08:18:47+00:00"
+        );
+        assert_eq!(parse("2026-01-30T08:18").to_string(), "2026-01-30T08:18:00");
+    }
+
+    #[test]
+    fn json_keeps_the_offset() {
+        let date = parse("2026-01-30T08:18:47.998-05:00");
+        assert_eq!(
+            serde_json::to_string(&date).unwrap(),
+            "\"2026-01-30T08:18:47.998-05:00\""
+        );
+    }
+
+    #[test]
+    fn date_only_values_sort_after_timed_ones_that_day() {
+        let day = parse("2026-03-01");
+        let morning = parse("2026-03-01T09:00");
+        assert!(morning < day);
+        assert!(day < parse("2026-03-02T00:00"));
+        assert_eq!(day.date(), NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+    }
+
+    #[test]
+    fn date_only_values_pass_at_the_end_of_the_day() {
+        let day = parse("2026-03-01");
+        let noon = NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert!(!day.is_before(noon));
+        assert!(parse("2026-03-01T09:00").is_before(noon));
+        assert!(day.is_before(noon + chrono::Days::new(1)));
+    }
+
+    #[test]
+    fn dates_and_datetimes_parse_to_their_own_variants() {
+        let day = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(parse("2024-06-01"), TaskDate::Date(day));
+        assert_eq!(parse(" 2024-06-01 ").time(), None);
+        for s in [
+            "2024-06-01T14:30",
+            "2024-06-01 14:30",
+            "2024-06-01T14:30:00",
+        ] {
+            assert_eq!(
+                parse(s),
+                TaskDate::DateTime(day.and_hms_opt(14, 30, 0).unwrap()),
+                "{}",
+                s
+            );
+        }
+        assert!("2024-06-01T25:00".parse::<TaskDate>().is_err());
+        assert!("June 1st".parse::<TaskDate>().is_err());
+    }
+
+    #[test]
+    fn datetimes_pass_at_their_time_of_day() {
+        let due = parse("2024-06-01T14:00");
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 6, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        assert!(!due.is_before(at(13, 59)));
+        assert!(!due.is_before(at(14, 0)));
+        assert!(due.is_before(at(14, 1)));
+        assert_eq!(due.date(), at(0, 0).date());
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Dates {
+        #[serde(default, deserialize_with = "super::deserialize_optional")]
+        due: Option<TaskDate>,
+        #[serde(
+            rename = "completedDate",
+            default,
+            deserialize_with = "super::deserialize_optional"
+        )]
+        completed_date: Option<NaiveDate>,
+    }
+
+    fn dates(yaml: &str) -> Result<Dates, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    #[test]
+    fn quoted_bare_and_blank_dates_in_frontmatter() {
+        let day = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        for value in ["\"2026-01-02\"", "2026-01-02", "' 2026-01-02 '"] {
+            let d = dates(&format!("due: {0}\ncompletedDate: {0}", value)).unwrap();
+            assert_eq!(d.due.map(|due| due.date()), Some(day), "{}", value);
+            assert_eq!(d.completed_date, Some(day), "{}", value);
+        }
+        for value in ["", "\"\"", "'  '", "null"] {
+            let d = dates(&format!("due: {0}\ncompletedDate: {0}", value)).unwrap();
+            assert!(d.due.is_none() && d.completed_date.is_none(), "{}", value);
+        }
+        let d = dates("other: 1").unwrap();
+        assert!(d.due.is_none() && d.completed_date.is_none());
+    }
+
+    #[test]
+    fn unreadable_dates_name_the_value() {
+        let err = dates("due: someday").unwrap_err().to_string();
+        assert!(err.contains("invalid date `someday`"), "{}", err);
+        let err = dates("completedDate: 2026-01-02T09:00")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid date `2026-01-02T09:00`"), "{}", err);
+    }
+}
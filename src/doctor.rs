@@ -0,0 +1,140 @@
+//! Setup diagnostics for `doctor`: is the path right, and do the notes parse?
+
+use crate::{parse_task_files, scan_roots, task_files, ScanOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// What a scan of one folder found
+#[derive(Debug, Serialize)]
+pub struct RootReport {
+    pub path: PathBuf,
+    pub exists: bool,
+    pub md_files: usize,
+}
+
+/// A note that could not be parsed, and why
+#[derive(Debug, Serialize)]
+pub struct FailedFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Everything `doctor` checks about a vault path
+#[derive(Debug, Serialize)]
+pub struct Diagnosis {
+    pub path: PathBuf,
+    pub exists: bool,
+    pub is_dir: bool,
+    /// The `Archive` folder next to the path, when one exists
+    pub archive_sibling: Option<PathBuf>,
+    /// Whether that sibling is scanned (it isn't with `--no-archive`)
+    pub archive_sibling_scanned: bool,
+    pub roots: Vec<RootReport>,
+    pub md_files: usize,
+    pub parsed: usize,
+    pub failed: Vec<FailedFile>,
+}
+
+impl Diagnosis {
+    /// Check `vault_path` and parse every note a scan would read
+    pub fn run(vault_path: &Path, options: &ScanOptions) -> Diagnosis {
+        let archive_sibling = vault_path
+            .parent()
+            .map(|parent| parent.join("Archive"))
+            .filter(|archive| archive.is_dir() && archive != vault_path);
+
+        let mut roots = Vec::new();
+        let mut parsed = 0;
+        let mut failed = Vec::new();
+        for root in scan_roots(vault_path, options) {
+            let paths = task_files(&root, options);
+            for (path, result) in paths.iter().zip(parse_task_files(
+                &paths,
+                options.threads(),
+                options.default_status(),
+            )) {
+                match result {
+                    Ok(_) => parsed += 1,
+                    Err(e) => failed.push(FailedFile {
+                        path: path.clone(),
+                        error: format!("{:#}", e),
+                    }),
+                }
+            }
+            roots.push(RootReport {
+                exists: root.is_dir(),
+                md_files: paths.len(),
+                path: root,
+            });
+        }
+
+        Diagnosis {
+            path: vault_path.to_path_buf(),
+            exists: vault_path.exists(),
+            is_dir: vault_path.is_dir(),
+            archive_sibling_scanned: archive_sibling.is_some() && !options.no_sibling_archive,
+            archive_sibling,
+            md_files: roots.iter().map(|r| r.md_files).sum(),
+            roots,
+            parsed,
+            failed,
+        }
+    }
+
+    /// Whether anything needs the user's attention
+    pub fn is_healthy(&self) -> bool {
+        self.is_dir && self.md_files > 0 && self.failed.is_empty()
+    }
+
+    /// A checklist for humans, ending with a hint when something looks wrong
+    pub fn render_plain(&self) -> String {
+        let mark = |ok: bool| if ok { "ok " } else { "!! " };
+        let mut lines = vec![format!(
+            "{}path: {}{}",
+            mark(self.is_dir),
+            self.path.display(),
+            match (self.exists, self.is_dir) {
+                (false, _) => " (does not exist)",
+                (true, false) => " (not a directory)",
+                _ => "",
+            }
+        )];
+        lines.push(match &self.archive_sibling {
+            Some(archive) if self.archive_sibling_scanned => {
+                format!("ok archive sibling: {}", archive.display())
+            }
+            Some(archive) => format!("-- archive sibling: {} (skipped)", archive.display()),
+            None => "-- archive sibling: none".to_string(),
+        });
+        for root in &self.roots {
+            lines.push(format!(
+                "{}scanned {}: {} .md file(s){}",
+                mark(root.exists),
+                root.path.display(),
+                root.md_files,
+                if root.exists { "" } else { " (missing)" }
+            ));
+        }
+        lines.push(format!(
+            "{}parsed {} of {} file(s)",
+            mark(self.md_files > 0 && self.failed.is_empty()),
+            self.parsed,
+            self.md_files
+        ));
+        for failure in &self.failed {
+            lines.push(format!("   {}: {}", failure.path.display(), failure.error));
+        }
+
+        if !self.exists {
+            lines.push(
+                "\nhint: --path should point at the TaskNotes folder inside your vault".into(),
+            );
+        } else if self.md_files == 0 {
+            lines
+                .push("\nhint: no .md files found; check --path and any --exclude patterns".into());
+        } else if !self.failed.is_empty() {
+            lines.push("\nhint: run `validate` for field-by-field details on the failures".into());
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,89 @@
+//! iCalendar (RFC 5545) export of tasks with a due date.
+
+use crate::{Task, TaskDate};
+use chrono::{Days, Utc};
+
+/// Render tasks with a due date as an iCalendar feed of VEVENTs. Tasks without
+/// a `due` are skipped. Date-only dues become all-day events.
+pub fn render_ics(tasks: &[&Task]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//obsidian-tasks//TaskNotes//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for task in tasks {
+        let Some(due) = task.due else {
+            continue;
+        };
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event_uid(task)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        match due {
+            TaskDate::Date(date) => {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+                let end = date + Days::new(1);
+                lines.push(format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+            }
+            TaskDate::DateTime(dt) => {
+                // Floating local time, matching how the frontmatter was written
+                lines.push(format!("DTSTART:{}", dt.format("%Y%m%dT%H%M%S")));
+            }
+            TaskDate::Offset(dt) => {
+                // A fixed instant, so written in UTC
+                let utc = dt.with_timezone(&Utc);
+                lines.push(format!("DTSTART:{}", utc.format("%Y%m%dT%H%M%SZ")));
+            }
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(task.display_name())));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// Stable identifier built from the filename and creation date
+fn event_uid(task: &Task) -> String {
+    let created = task
+        .date_created
+        .map(|d| d.to_string())
+        .unwrap_or_else(|| "undated".to_string());
+    let raw = format!("{}-{}", task.filename, created);
+    let id: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}@obsidian-tasks", id)
+}
+
+/// Escape a TEXT value per RFC 5545 section 3.3.11
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold content lines longer than 75 octets onto continuation lines
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+    folded
+}
@@ -0,0 +1,2058 @@
+//! Parsing and querying of Obsidian TaskNotes.
+//!
+//! The `obsidian-tasks` binary is a thin CLI over this crate; everything needed
+//! to load a vault and filter its tasks lives here.
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use cache::TaskCache;
+pub use date::TaskDate;
+pub use status::{StatusKind, Statuses};
+
+pub mod cache;
+pub mod calendar;
+pub mod clock;
+pub mod config;
+pub mod date;
+pub mod doctor;
+pub mod ics;
+pub mod note;
+pub mod output;
+pub mod pick;
+pub mod priority;
+pub mod recurrence;
+pub mod regex;
+pub mod render;
+pub mod schema;
+pub mod stats;
+pub mod status;
+#[cfg(test)]
+mod testutil;
+pub mod toml;
+pub mod tz;
+pub mod validate;
+pub mod vault;
+pub mod watch;
+pub mod waybar;
+
+/// How filters compare tags, projects, priorities, contexts and source types
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    /// `Work` and `work` are the same
+    #[default]
+    Insensitive,
+    /// Only identical spellings match
+    Sensitive,
+}
+
+impl CaseMode {
+    pub fn eq(self, a: &str, b: &str) -> bool {
+        match self {
+            CaseMode::Insensitive => a.to_lowercase() == b.to_lowercase(),
+            CaseMode::Sensitive => a == b,
+        }
+    }
+
+    /// `s` in the form compared under this mode
+    pub fn fold(self, s: &str) -> String {
+        match self {
+            CaseMode::Insensitive => s.to_lowercase(),
+            CaseMode::Sensitive => s.to_string(),
+        }
+    }
+}
+
+/// A single TaskNote, deserialized from its YAML frontmatter. Unknown keys,
+/// including nested maps and lists under them, are deliberately ignored so
+/// notes carrying extra plugin metadata still parse.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Task {
+    /// File stem of the note; filled in after parsing rather than read from frontmatter
+    #[serde(skip_deserializing)]
+    pub filename: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Required; only left empty by deserialization so a missing one can be
+    /// reported, or filled in by [`Frontmatter::parse_with_default`]
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(
+        rename = "dateCreated",
+        default,
+        deserialize_with = "date::deserialize_lenient"
+    )]
+    pub date_created: Option<TaskDate>,
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_list")]
+    pub projects: Vec<String>,
+    /// GTD-style contexts such as `@home`; the `@` is optional
+    #[serde(default, deserialize_with = "deserialize_list")]
+    pub contexts: Vec<String>,
+    #[serde(default, deserialize_with = "date::deserialize_optional")]
+    pub due: Option<TaskDate>,
+    #[serde(default, deserialize_with = "date::deserialize_optional")]
+    pub scheduled: Option<TaskDate>,
+    /// Earliest day work on the task can begin
+    #[serde(
+        alias = "startDate",
+        default,
+        deserialize_with = "date::deserialize_optional"
+    )]
+    pub start: Option<NaiveDate>,
+    #[serde(
+        rename = "completedDate",
+        default,
+        deserialize_with = "date::deserialize_optional"
+    )]
+    pub completed_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// Estimated effort in minutes
+    #[serde(rename = "timeEstimate", default)]
+    pub time_estimate: Option<u32>,
+    #[serde(rename = "taskSourceType", default)]
+    pub task_source_type: Option<String>,
+    /// Filenames (or wikilinks) of tasks that must be finished before this one
+    #[serde(
+        rename = "dependsOn",
+        alias = "depends_on",
+        default,
+        deserialize_with = "deserialize_list"
+    )]
+    pub depends_on: Vec<String>,
+    /// How `status` was classified during the scan; the default vocabulary is
+    /// used when the task hasn't been through [`Task::classify`]
+    #[serde(skip)]
+    pub status_kind: Option<StatusKind>,
+    /// Note content after the frontmatter block
+    #[serde(skip)]
+    pub body: String,
+    /// The note file this task was read from
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// Last modification time of the note file, when it was read from disk
+    #[serde(skip)]
+    pub modified: Option<SystemTime>,
+    /// Whether the note was found in the main folder or an archive
+    #[serde(skip)]
+    pub source: SourceKind,
+}
+
+/// Deserialize a list field written either as a YAML sequence or as a single
+/// comma-separated string (`projects: Proj A, Proj B`). Entries are trimmed
+/// and empty ones dropped; null is an empty list.
+fn deserialize_list<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    list_entries(deserializer, |s| s.split(',').map(str::to_string).collect())
+}
+
+/// Like [`deserialize_list`], but a string also splits on whitespace, since a
+/// tag can't contain spaces (`tags: work urgent`)
+fn deserialize_tags<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    list_entries(deserializer, |s| {
+        s.split(|c: char| c == ',' || c.is_whitespace())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+fn list_entries<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+    split: impl Fn(&str) -> Vec<String>,
+) -> Result<Vec<String>, D::Error> {
+    use serde_yaml::Value;
+    let scalar = |value: Value| match value {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a list of strings, found {:?}",
+            other
+        ))),
+    };
+    let entries = match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Sequence(items)) => items
+            .into_iter()
+            .filter(|item| !item.is_null())
+            .map(scalar)
+            .collect::<Result<_, _>>()?,
+        Some(value) => split(&scalar(value)?),
+    };
+    Ok(entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect())
+}
+
+/// The kind of folder a task was found in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Under a `--path` folder, outside any `Archive` subfolder
+    #[default]
+    Primary,
+    /// In an `Archive` folder: the sibling of `--path`, an `--archive` folder,
+    /// or an `Archive` subfolder of `--path`
+    Archive,
+}
+
+impl SourceKind {
+    /// Where the note at `path`, found while scanning `root`, lives
+    pub fn of(root: &Path, path: &Path, root_is_archive: bool) -> SourceKind {
+        let in_archive_folder = path.strip_prefix(root).is_ok_and(|relative| {
+            relative
+                .parent()
+                .is_some_and(|dir| dir.iter().any(|c| c.eq_ignore_ascii_case("archive")))
+        });
+        if root_is_archive || in_archive_folder {
+            SourceKind::Archive
+        } else {
+            SourceKind::Primary
+        }
+    }
+}
+
+impl Task {
+    /// The frontmatter `title` when present, otherwise the filename
+    pub fn display_name(&self) -> &str {
+        match self.title.as_deref().map(str::trim) {
+            Some(title) if !title.is_empty() => title,
+            _ => &self.filename,
+        }
+    }
+
+    /// Classify `status` against a custom vocabulary
+    pub fn classify(&mut self, statuses: &Statuses) {
+        self.status_kind = Some(statuses.classify(&self.status));
+    }
+
+    pub fn status_kind(&self) -> StatusKind {
+        self.status_kind
+            .unwrap_or_else(|| Statuses::default().classify(&self.status))
+    }
+
+    /// Replace `status` with its canonical spelling (see [`status::canonical`]),
+    /// keeping the kind it was classified as
+    pub fn normalize_status(&mut self) {
+        let kind = self.status_kind();
+        self.status_kind = Some(kind);
+        self.status = status::canonical(&self.status, kind).to_string();
+    }
+
+    /// Checkbox shown before the task in plain and table output, e.g. `[x]`
+    /// for done; see [`status::symbol`]
+    pub fn status_symbol(&self) -> &str {
+        status::symbol(&self.status, self.status_kind())
+    }
+
+    /// Whether the task is open and its status says work has started, e.g.
+    /// `in-progress`; see [`status::IN_PROGRESS`]
+    pub fn is_in_progress(&self) -> bool {
+        self.is_open() && status::is_in_progress(&self.status)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status_kind() == StatusKind::Done
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status_kind() == StatusKind::Cancelled
+    }
+
+    /// Neither done nor cancelled
+    pub fn is_open(&self) -> bool {
+        self.status_kind() == StatusKind::Open
+    }
+
+    pub fn has_priority(&self, wanted: &[String], case: CaseMode) -> bool {
+        if wanted.is_empty() {
+            return true;
+        }
+        match &self.priority {
+            Some(p) => wanted.iter().any(|w| case.eq(w.trim(), p.trim())),
+            None => false,
+        }
+    }
+
+    /// Ordinal used for priority sorting; unknown or missing priorities rank lowest.
+    /// See [`priority::rank`].
+    pub fn priority_rank(&self) -> usize {
+        priority::rank(self.priority.as_deref())
+    }
+
+    /// Whether the task's priority is `level` or higher. Tasks without a
+    /// known priority never are.
+    pub fn priority_at_least(&self, level: &str) -> bool {
+        let rank = self.priority_rank();
+        rank > 0 && rank >= priority::rank(Some(level))
+    }
+
+    pub fn matches_tags(&self, tags: &[String], prefix: bool, case: CaseMode) -> bool {
+        if tags.is_empty() {
+            return true;
+        }
+        tags.iter().any(|wanted| {
+            let wanted = case.fold(wanted.trim_start_matches('#'));
+            self.tags.iter().any(|tag| {
+                let tag = case.fold(tag.trim_start_matches('#'));
+                tag == wanted || (prefix && tag.starts_with(&format!("{}/", wanted)))
+            })
+        })
+    }
+
+    /// Whether the task has one of `wanted` contexts, ignoring a leading `@` on
+    /// either side. An empty `wanted` matches everything.
+    pub fn has_context(&self, wanted: &[String], case: CaseMode) -> bool {
+        if wanted.is_empty() {
+            return true;
+        }
+        let normalize = |c: &str| case.fold(c.trim().trim_start_matches('@'));
+        wanted.iter().any(|wanted| {
+            let wanted = normalize(wanted);
+            self.contexts.iter().any(|c| normalize(c) == wanted)
+        })
+    }
+
+    /// Whether `taskSourceType` is `wanted`. Tasks without one never match.
+    pub fn has_source_type(&self, wanted: &str, case: CaseMode) -> bool {
+        self.task_source_type
+            .as_deref()
+            .is_some_and(|s| case.eq(s.trim(), wanted.trim()))
+    }
+
+    pub fn in_project(&self, name: &str, case: CaseMode) -> bool {
+        let name = project_name(name);
+        self.projects.iter().any(|p| case.eq(project_name(p), name))
+    }
+
+    pub fn is_due_today(&self, today: NaiveDate) -> bool {
+        if let Some(due) = self.due {
+            due.date() == today
+        } else {
+            false
+        }
+    }
+
+    pub fn is_scheduled_today(&self, today: NaiveDate) -> bool {
+        if let Some(scheduled) = self.scheduled {
+            scheduled.date() == today
+        } else {
+            false
+        }
+    }
+
+    /// Whether the task may be worked on by `today`: its `start` date, or its
+    /// `scheduled` day when it has no `start`, isn't after `today`. Tasks with
+    /// neither have always started.
+    pub fn has_started(&self, today: NaiveDate) -> bool {
+        self.start
+            .or(self.scheduled.map(|s| s.date()))
+            .is_none_or(|start| start <= today)
+    }
+
+    /// Whether the task has neither a `due` nor a `scheduled` date
+    pub fn is_undated(&self) -> bool {
+        self.due.is_none() && self.scheduled.is_none()
+    }
+
+    /// Open and due before `now`, a wall-clock time in the configured zone;
+    /// date-only due dates only pass once their day is over
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        if let Some(due) = self.due {
+            self.is_open() && due.is_before(now)
+        } else {
+            false
+        }
+    }
+
+    /// Due within `after..=before` (by day); a missing bound leaves that side
+    /// open. Tasks without a due date never match.
+    pub fn due_in_range(&self, after: Option<NaiveDate>, before: Option<NaiveDate>) -> bool {
+        match self.due.map(|d| d.date()) {
+            Some(due) => after.is_none_or(|a| due >= a) && before.is_none_or(|b| due <= b),
+            None => false,
+        }
+    }
+
+    /// Done, but completed on a later day than it was due
+    pub fn was_completed_late(&self) -> bool {
+        match (self.due, self.completed_date) {
+            (Some(due), Some(completed)) => self.is_done() && completed > due.date(),
+            _ => false,
+        }
+    }
+
+    /// Open and due from the day after `today` up to `days` days later
+    pub fn is_due_within(&self, days: u32, today: NaiveDate) -> bool {
+        if let Some(due) = self.due {
+            let due = due.date();
+            self.is_open() && due > today && due <= today + chrono::Days::new(days as u64)
+        } else {
+            false
+        }
+    }
+
+    /// Whether the task is open and due in the week holding `today`, which
+    /// begins on `week_start` (Monday for ISO weeks). Unlike
+    /// [`Task::is_due_within`] this includes days earlier in the week, so it
+    /// still holds once overdue.
+    pub fn is_due_this_week(&self, week_start: Weekday, today: NaiveDate) -> bool {
+        let week = today.week(week_start);
+        self.is_open()
+            && self
+                .due
+                .is_some_and(|due| (week.first_day()..=week.last_day()).contains(&due.date()))
+    }
+
+    /// Virtual copies of a recurring task, one per occurrence in `from..=to`, each
+    /// with `due` moved to that day. The series is anchored at the task's due (or
+    /// scheduled) date. Returns `None` for non-recurring tasks.
+    pub fn expand_recurring(&self, from: NaiveDate, to: NaiveDate) -> Option<Result<Vec<Task>>> {
+        let rule = self.recurrence.as_deref()?.trim();
+        let anchor = self.due.or(self.scheduled)?;
+        if rule.is_empty() {
+            return None;
+        }
+
+        let rule: recurrence::RRule = match rule.parse() {
+            Ok(rule) => rule,
+            Err(e) => return Some(Err(e)),
+        };
+        let instances = rule
+            .occurrences(anchor.date(), from, to)
+            .into_iter()
+            .map(|date| {
+                let mut instance = self.clone();
+                instance.due = Some(match anchor.time() {
+                    Some(time) => TaskDate::DateTime(date.and_time(time)),
+                    None => TaskDate::Date(date),
+                });
+                instance
+            })
+            .collect();
+        Some(Ok(instances))
+    }
+
+    /// Whole days from `today` to the due date: negative once overdue, `None` without one
+    pub fn days_until_due(&self, today: NaiveDate) -> Option<i64> {
+        Some((self.due?.date() - today).num_days())
+    }
+
+    /// Whole days from `dateCreated` to `today`, or `None` without a valid creation date
+    pub fn age_days(&self, today: NaiveDate) -> Option<i64> {
+        let created = self.date_created?.date();
+        Some((today - created).num_days())
+    }
+
+    /// Whether the note file was modified at or after `threshold`, a wall-clock
+    /// time in the configured zone. Tasks not read from disk never match.
+    pub fn modified_since(&self, threshold: NaiveDateTime) -> bool {
+        self.modified
+            .is_some_and(|m| clock::to_local(m.into(), clock::timezone()) >= threshold)
+    }
+
+    /// The note body without trailing line breaks, as shown in JSON output
+    pub fn body_text(&self) -> &str {
+        self.body.trim_end_matches(['\n', '\r'])
+    }
+
+    /// Whether the title (or filename) or body contains `query`, ignoring case
+    pub fn contains_text(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.display_name().to_lowercase().contains(&query)
+            || self.body.to_lowercase().contains(&query)
+    }
+
+    /// Whether the title (or filename) or body matches `pattern`
+    pub fn matches_regex(&self, pattern: &regex::Regex) -> bool {
+        pattern.is_match(self.display_name()) || pattern.is_match(&self.body)
+    }
+
+    /// Completed within `since..=until`; a missing bound leaves that side open
+    pub fn completed_between(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
+        match self.completed_date {
+            Some(completed) => {
+                since.is_none_or(|s| completed >= s) && until.is_none_or(|u| completed <= u)
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_completed_today(&self, today: NaiveDate) -> bool {
+        if let Some(completed) = self.completed_date {
+            completed == today
+        } else {
+            false
+        }
+    }
+
+    /// Whether `dateCreated` falls on `today`, ignoring its time of day
+    pub fn is_created_today(&self, today: NaiveDate) -> bool {
+        self.date_created
+            .is_some_and(|created| created.date() == today)
+    }
+}
+
+/// Urgency ordering as of `now`, most urgent first: overdue tasks before the
+/// rest, then earliest due date (undated last), then highest priority.
+pub fn cmp_urgency(a: &Task, b: &Task, now: NaiveDateTime) -> std::cmp::Ordering {
+    b.is_overdue(now)
+        .cmp(&a.is_overdue(now))
+        .then_with(|| match (a.due, b.due) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        })
+        .then_with(|| b.priority_rank().cmp(&a.priority_rank()))
+}
+
+/// The single most urgent open task as of `now`, if any, according to [`cmp_urgency`]
+pub fn most_urgent(tasks: &[Task], now: NaiveDateTime) -> Option<&Task> {
+    tasks
+        .iter()
+        .filter(|t| t.is_open())
+        .min_by(|a, b| cmp_urgency(a, b, now))
+}
+
+/// Sum of `timeEstimate` minutes over `tasks`; tasks without an estimate count as zero
+pub fn total_effort<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> u64 {
+    tasks
+        .into_iter()
+        .filter_map(|t| t.time_estimate)
+        .map(u64::from)
+        .sum()
+}
+
+/// Strip Obsidian wikilink syntax so `[[Home Reno|Reno]]` becomes `Home Reno`
+pub fn project_name(raw: &str) -> &str {
+    let name = raw.trim();
+    let name = name
+        .strip_prefix("[[")
+        .and_then(|n| n.strip_suffix("]]"))
+        .unwrap_or(name);
+    name.split('|').next().unwrap_or(name).trim()
+}
+
+/// The syntax a frontmatter block is written in, told apart by its delimiter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterFormat {
+    /// Delimited by `---`, as Obsidian writes it
+    Yaml,
+    /// Delimited by `+++`, as Hugo and Zola write it
+    Toml,
+}
+
+impl FrontmatterFormat {
+    pub fn delimiter(self) -> &'static str {
+        match self {
+            FrontmatterFormat::Yaml => "---",
+            FrontmatterFormat::Toml => "+++",
+        }
+    }
+
+    fn from_delimiter(line: &str) -> Option<FrontmatterFormat> {
+        [FrontmatterFormat::Yaml, FrontmatterFormat::Toml]
+            .into_iter()
+            .find(|format| line.trim_end() == format.delimiter())
+    }
+}
+
+impl std::fmt::Display for FrontmatterFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FrontmatterFormat::Yaml => "YAML",
+            FrontmatterFormat::Toml => "TOML",
+        })
+    }
+}
+
+/// A note's frontmatter block, without its delimiters
+#[derive(Debug, Clone)]
+pub struct Frontmatter {
+    pub format: FrontmatterFormat,
+    pub text: String,
+}
+
+impl Frontmatter {
+    /// Deserialize the block into a task with the parser for its format
+    pub fn parse(&self) -> Result<Task> {
+        self.parse_with_default(None)
+    }
+
+    /// Like [`Frontmatter::parse`], but a block without a `status`, including
+    /// an empty one such as a freshly created note's, gets `default_status`
+    /// instead of being rejected
+    pub fn parse_with_default(&self, default_status: Option<&str>) -> Result<Task> {
+        let mut task = match self.format {
+            FrontmatterFormat::Yaml => parse_frontmatter_yaml(&self.text)?,
+            FrontmatterFormat::Toml => parse_frontmatter_toml(&self.text)?,
+        };
+        if task.status.trim().is_empty() {
+            match default_status {
+                Some(status) => task.status = status.to_string(),
+                None if self.text.trim().is_empty() => {
+                    anyhow::bail!("the frontmatter block is empty, so there is no `status`")
+                }
+                None => anyhow::bail!("missing field `status`"),
+            }
+        }
+        Ok(task)
+    }
+}
+
+pub fn extract_frontmatter(content: &str) -> Option<String> {
+    split_frontmatter(content).map(|(frontmatter, _)| frontmatter.text)
+}
+
+/// Split a note into its frontmatter and the body that follows it. The block
+/// is YAML between `---` lines or TOML between `+++` lines, and must be closed
+/// by the same delimiter it was opened with.
+pub fn split_frontmatter(content: &str) -> Option<(Frontmatter, String)> {
+    // Editors on Windows often prefix files with a byte-order mark
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    // `lines()` only splits on `\n` and `\r\n`; old Mac files end lines with a
+    // bare `\r`, which would otherwise leave the whole note on one line
+    let content = if content.contains('\r') {
+        std::borrow::Cow::Owned(content.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        std::borrow::Cow::Borrowed(content)
+    };
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Some export tools prepend blank lines before the opening delimiter
+    let start = lines.iter().position(|line| !line.trim().is_empty())?;
+    let format = FrontmatterFormat::from_delimiter(lines[start])?;
+
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim_end() == format.delimiter() {
+            let frontmatter = Frontmatter {
+                format,
+                text: lines[start + 1..i].join("\n"),
+            };
+            return Some((frontmatter, lines[i + 1..].join("\n")));
+        }
+    }
+
+    None
+}
+
+/// Deserialize a task from YAML frontmatter. Only the first YAML document is
+/// used, so a stray `---` separator inside the block doesn't reject the note.
+pub fn parse_frontmatter_yaml(frontmatter: &str) -> Result<Task> {
+    let document = serde_yaml::Deserializer::from_str(frontmatter)
+        .next()
+        .context("Frontmatter contains no YAML document")?;
+    Ok(Task::deserialize(document)?)
+}
+
+/// Deserialize a task from TOML frontmatter, using the same field names as YAML
+pub fn parse_frontmatter_toml(frontmatter: &str) -> Result<Task> {
+    toml::from_str_as(frontmatter)
+}
+
+/// The filename a task is known by: the note's file stem
+pub(crate) fn task_filename(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+pub fn parse_task_file(path: &Path) -> Result<Task> {
+    parse_task_file_with(path, None)
+}
+
+/// Parse the note at `path`, giving it `default_status` if its frontmatter
+/// has none; see [`Frontmatter::parse_with_default`]
+pub fn parse_task_file_with(path: &Path, default_status: Option<&str>) -> Result<Task> {
+    let content =
+        read_note(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+
+    let (frontmatter, body) = split_frontmatter(&content).context("No frontmatter found")?;
+
+    let mut task = frontmatter
+        .parse_with_default(default_status)
+        .with_context(|| {
+            format!(
+                "Failed to parse {} in: {}",
+                frontmatter.format,
+                path.display()
+            )
+        })?;
+
+    task.filename = task_filename(path);
+    task.body = body;
+    task.path = path.to_path_buf();
+    task.modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    Ok(task)
+}
+
+/// How long to wait before reading a note a second time
+const READ_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Read a note, trying once more after a short pause if the read fails or
+/// the file changed size or mtime while it was being read. A sync client
+/// rewriting the note can briefly leave it missing or truncated between the
+/// walk listing it and the read; a note that is simply empty is read once.
+fn read_note(path: &Path) -> std::io::Result<String> {
+    let stamp = |path: &Path| fs::metadata(path).map(|m| (m.len(), m.modified().ok()));
+    let before = stamp(path);
+    let first = fs::read_to_string(path);
+    let settled = match (&first, &before) {
+        (Ok(content), Ok((len, _))) => {
+            *len == content.len() as u64 && stamp(path).ok() == before.ok()
+        }
+        _ => false,
+    };
+    if settled {
+        return first;
+    }
+    std::thread::sleep(READ_RETRY_DELAY);
+    fs::read_to_string(path).or(first)
+}
+
+/// Parse a whole note held in memory, e.g. piped in on stdin, as if it were
+/// a file named `filename`
+pub fn parse_task_str(content: &str, filename: &str) -> Result<Task> {
+    parse_task_str_default(content, filename, None)
+}
+
+/// Like [`parse_task_str`], but read the way a scan with `options` reads a
+/// file: a missing status becomes `--default-status`, and the status is
+/// classified (and checked for typos) against the configured vocabulary
+pub fn parse_task_str_with(content: &str, filename: &str, options: &ScanOptions) -> Result<Task> {
+    let mut task = parse_task_str_default(content, filename, options.default_status())?;
+    options.classify(&mut task, Path::new(filename));
+    Ok(task)
+}
+
+fn parse_task_str_default(
+    content: &str,
+    filename: &str,
+    default_status: Option<&str>,
+) -> Result<Task> {
+    let (frontmatter, body) = split_frontmatter(content).context("No frontmatter found")?;
+
+    let mut task = frontmatter
+        .parse_with_default(default_status)
+        .with_context(|| format!("Failed to parse {}", frontmatter.format))?;
+    task.filename = filename.to_string();
+    task.body = body;
+
+    Ok(task)
+}
+
+/// A task file that could not be read or parsed
+pub struct ParseFailure {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+impl ParseFailure {
+    /// Whether the file couldn't be read at all, usually because it changed
+    /// or vanished mid-scan, as opposed to holding bad frontmatter
+    pub fn is_io(&self) -> bool {
+        self.error.chain().any(|e| e.is::<std::io::Error>())
+    }
+}
+
+/// A note left out of a scan, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanWarning {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Parse every file in `paths`, spreading the work over `threads` workers.
+/// Results come back in the same order as `paths`, whatever the thread count.
+/// Notes without a status get `default_status`, if given.
+pub fn parse_task_files(
+    paths: &[PathBuf],
+    threads: usize,
+    default_status: Option<&str>,
+) -> Vec<Result<Task>> {
+    let parse = |p: &PathBuf| parse_task_file_with(p, default_status);
+    let threads = threads.clamp(1, paths.len().max(1));
+    if threads == 1 {
+        return paths.iter().map(parse).collect();
+    }
+
+    let chunk_size = paths.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(parse).collect::<Vec<_>>()))
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("task parser thread panicked"))
+            .collect()
+    })
+}
+
+/// All task note files under `root`, in walk order, honoring the exclude,
+/// symlink and depth settings in `options`
+pub fn task_files(root: &Path, options: &ScanOptions) -> Vec<PathBuf> {
+    if !root.exists() || !root.is_dir() {
+        return Vec::new();
+    }
+
+    WalkDir::new(root)
+        .follow_links(options.follow_links)
+        .max_depth(MAX_WALK_DEPTH)
+        .into_iter()
+        .filter_entry(|e| !options.is_excluded(root, e.path()))
+        // Unreadable folders are skipped rather than failing the whole scan
+        .filter_map(|e| match e {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                if !options.quiet {
+                    warn_walk_error(root, &error);
+                }
+                None
+            }
+        })
+        .filter(|e| options.is_note(e.path()))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+fn warn_walk_error(root: &Path, error: &walkdir::Error) {
+    let path = error.path().unwrap_or(root);
+    match error.io_error() {
+        Some(io) => eprintln!("warning: skipping {}: {}", path.display(), io),
+        None => eprintln!("warning: skipping {}: {}", path.display(), error),
+    }
+}
+
+/// Helper to scan a directory for .md files and add them to the tasks vector.
+/// Files unchanged since they were stored in `cache` are not parsed again.
+pub fn scan_dir(
+    path: &Path,
+    options: &ScanOptions,
+    mut cache: Option<&mut TaskCache>,
+    tasks: &mut Vec<Task>,
+    failures: &mut Vec<ParseFailure>,
+) {
+    let paths = task_files(path, options);
+    let mut cached: Vec<Option<Task>> = match cache.as_deref_mut() {
+        Some(cache) => paths.iter().map(|p| cache.get(p)).collect(),
+        None => vec![None; paths.len()],
+    };
+
+    let misses: Vec<PathBuf> = paths
+        .iter()
+        .zip(&cached)
+        .filter(|(_, hit)| hit.is_none())
+        .map(|(p, _)| p.clone())
+        .collect();
+    let mut parsed =
+        parse_task_files(&misses, options.threads(), options.default_status()).into_iter();
+
+    for (path, hit) in paths.iter().zip(cached.iter_mut()) {
+        let result = match hit.take() {
+            Some(task) => Ok(task),
+            None => {
+                let result = parsed.next().expect("one parse result per cache miss");
+                // A status filled in from --default-status isn't the note's own,
+                // so don't let it outlive a run without the flag
+                if let (Some(cache), Ok(task)) = (cache.as_deref_mut(), &result) {
+                    if options.default_status() != Some(task.status.as_str()) {
+                        cache.insert(path, task);
+                    }
+                }
+                result
+            }
+        };
+        match result {
+            Ok(mut task) => {
+                options.classify(&mut task, path);
+                tasks.push(task);
+            }
+            Err(error) => failures.push(ParseFailure {
+                path: path.clone(),
+                error,
+            }),
+        }
+    }
+}
+
+/// What makes two scanned tasks the same task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DedupKey {
+    /// Same filename and `dateCreated`. Catches copies of a note in several
+    /// folders, but also merges different notes that happen to share both.
+    #[default]
+    FilenameCreated,
+    /// Same file on disk (canonical path). Only drops a note reached twice
+    /// through overlapping scan roots; copies elsewhere are all kept.
+    Path,
+    /// Keep every task found
+    None,
+}
+
+/// A copy dropped by [`dedup_tasks`], and the copy kept in its place
+#[derive(Debug, Clone)]
+pub struct DedupDecision {
+    pub kept: PathBuf,
+    pub dropped: PathBuf,
+}
+
+/// Drop repeated tasks according to `key`, e.g. when Archive is a subfolder
+/// of the scanned path and also picked up as a sibling. Of each set of copies
+/// the one [`cmp_copies`] prefers is kept, in the place of the first one seen,
+/// so the result doesn't depend on the order folders were walked in. Returns
+/// every copy dropped.
+pub fn dedup_tasks(tasks: &mut Vec<Task>, key: DedupKey) -> Vec<DedupDecision> {
+    match key {
+        DedupKey::FilenameCreated => {
+            dedup_tasks_by(tasks, |t| (t.filename.clone(), t.date_created))
+        }
+        DedupKey::Path => dedup_tasks_by(tasks, |t| {
+            fs::canonicalize(&t.path).unwrap_or(t.path.clone())
+        }),
+        DedupKey::None => Vec::new(),
+    }
+}
+
+fn dedup_tasks_by<K: std::hash::Hash + Eq>(
+    tasks: &mut Vec<Task>,
+    key: impl Fn(&Task) -> K,
+) -> Vec<DedupDecision> {
+    let mut slots: HashMap<K, usize> = HashMap::with_capacity(tasks.len());
+    let mut kept: Vec<Task> = Vec::with_capacity(tasks.len());
+    let mut decisions = Vec::new();
+    for task in tasks.drain(..) {
+        match slots.entry(key(&task)) {
+            Entry::Occupied(slot) => {
+                let current = &mut kept[*slot.get()];
+                let dropped = if cmp_copies(&task, current).is_gt() {
+                    std::mem::replace(current, task)
+                } else {
+                    task
+                };
+                decisions.push(DedupDecision {
+                    kept: current.path.clone(),
+                    dropped: dropped.path,
+                });
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(kept.len());
+                kept.push(task);
+            }
+        }
+    }
+    *tasks = kept;
+    decisions
+}
+
+/// Which of two copies of a task to keep (the greater): one outside the
+/// archive first, then the later `completedDate`, then the more recently
+/// modified file, and finally the smaller path so ties are still stable
+pub fn cmp_copies(a: &Task, b: &Task) -> std::cmp::Ordering {
+    let primary = |t: &Task| t.source == SourceKind::Primary;
+    primary(a)
+        .cmp(&primary(b))
+        .then(a.completed_date.cmp(&b.completed_date))
+        .then(a.modified.cmp(&b.modified))
+        .then(b.path.cmp(&a.path))
+}
+
+/// A `dependsOn` entry reduced to the filename it points at, so `[[Plan trip]]`,
+/// `Plan trip.md` and `Plan trip` all name the same note
+pub fn dependency_name(raw: &str) -> &str {
+    let name = project_name(raw);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+/// Which tasks are still open, by filename, for resolving `dependsOn`. Build it
+/// from the whole scan, before filters drop the tasks being depended on.
+pub struct Dependencies {
+    /// Whether every task with this filename is done or cancelled
+    finished: HashMap<String, bool>,
+}
+
+impl Dependencies {
+    pub fn new(tasks: &[Task]) -> Dependencies {
+        let mut finished: HashMap<String, bool> = HashMap::with_capacity(tasks.len());
+        for task in tasks {
+            let entry = finished.entry(task.filename.clone()).or_insert(true);
+            *entry &= !task.is_open();
+        }
+        Dependencies { finished }
+    }
+
+    /// Whether any of the task's dependencies is still open. A cancelled
+    /// dependency won't ever be done, so it no longer blocks; neither does a
+    /// reference to a task that doesn't exist (see [`Dependencies::dangling`]).
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.depends_on
+            .iter()
+            .any(|dep| self.finished.get(dependency_name(dep)) == Some(&false))
+    }
+
+    /// The task's `dependsOn` entries that don't match any scanned filename
+    pub fn dangling<'a>(&self, task: &'a Task) -> Vec<&'a str> {
+        task.depends_on
+            .iter()
+            .map(String::as_str)
+            .filter(|dep| !self.finished.contains_key(dependency_name(dep)))
+            .collect()
+    }
+}
+
+/// Minimal glob matcher over `/`-separated paths: `*` and `?` stay within one
+/// segment, while `**` spans any number of segments (including none).
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(p: &[char], s: &[char]) -> bool {
+        match p {
+            [] => s.is_empty(),
+            ['*', '*', '/', rest @ ..] => (0..=s.len())
+                .filter(|&i| i == 0 || s[i - 1] == '/')
+                .any(|i| matches(rest, &s[i..])),
+            ['*', '*', rest @ ..] => (0..=s.len()).any(|i| matches(rest, &s[i..])),
+            ['*', rest @ ..] => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != '/')
+                .any(|i| matches(rest, &s[i..])),
+            ['?', rest @ ..] => s.first().is_some_and(|&c| c != '/') && matches(rest, &s[1..]),
+            [c, rest @ ..] => s.first() == Some(c) && matches(rest, &s[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    matches(&pattern, &path)
+}
+
+/// Folder depth beyond which scanning stops, as a backstop against runaway
+/// symlinked trees when `follow_links` is enabled
+pub const MAX_WALK_DEPTH: usize = 64;
+
+/// Options controlling how a vault is scanned
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Fail with a report of every unparseable file instead of warning on stderr
+    pub strict: bool,
+    /// Number of parser threads; defaults to the available parallelism
+    pub threads: Option<usize>,
+    /// Paths or glob patterns, relative to the scanned folder, to skip while walking
+    pub exclude: Vec<String>,
+    /// Additional archive folders to scan alongside the main path
+    pub archives: Vec<PathBuf>,
+    /// Skip the automatic scan of an `Archive` folder next to the main path
+    pub no_sibling_archive: bool,
+    /// Follow symlinks while walking. Off by default since a link pointing back
+    /// into the vault would otherwise be walked again.
+    pub follow_links: bool,
+    /// Status strings counted as done or cancelled
+    pub statuses: Statuses,
+    /// Warn on stderr about statuses that look like typos of a done or cancelled one
+    pub fuzzy_status: bool,
+    /// How tasks found more than once are recognized and dropped
+    pub dedup: DedupKey,
+    /// JSON file caching parsed tasks between runs; see [`cache::TaskCache`]
+    pub cache: Option<PathBuf>,
+    /// Don't warn on stderr about unreadable folders or notes that fail to parse
+    pub quiet: bool,
+    /// Log each duplicate dropped by [`dedup_tasks`] to stderr
+    pub verbose: bool,
+    /// File extensions scanned as notes, without the dot; `md` when empty
+    pub extensions: Vec<String>,
+    /// Status given to notes whose frontmatter has none, instead of skipping them
+    pub default_status: Option<String>,
+}
+
+impl ScanOptions {
+    pub fn default_status(&self) -> Option<&str> {
+        self.default_status.as_deref()
+    }
+
+    /// Whether `path` has one of the note extensions, ignoring case
+    pub fn is_note(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        if self.extensions.is_empty() {
+            return ext.eq_ignore_ascii_case("md");
+        }
+        self.extensions
+            .iter()
+            .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    }
+
+    /// Whether `path` (found while walking `root`) matches one of the exclude patterns
+    fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(root) else {
+            return false;
+        };
+        let relative: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        if relative.is_empty() {
+            return false;
+        }
+        let relative = relative.join("/");
+
+        self.exclude.iter().any(|pattern| {
+            let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+            glob_match(pattern, &relative)
+        })
+    }
+
+    /// Classify the status of `task`, read from `source`, against `statuses`,
+    /// warning when `fuzzy_status` is set and it looks like a typo
+    fn classify(&self, task: &mut Task, source: &Path) {
+        task.classify(&self.statuses);
+        if self.fuzzy_status {
+            if let Some(known) = self.statuses.near_miss(&task.status) {
+                eprintln!(
+                    "warning: {}: status `{}` looks like a typo of `{}`",
+                    source.display(),
+                    task.status,
+                    known
+                );
+            }
+        }
+    }
+
+    fn threads(&self) -> usize {
+        self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+    }
+}
+
+/// The folders scanned for `vault_path`: the path itself, an `Archive` sibling
+/// when present, and any extra archives from `options`
+pub fn scan_roots(vault_path: &Path, options: &ScanOptions) -> Vec<PathBuf> {
+    // 1. The main TaskNotes directory (and its subfolders like Archive/)
+    let mut roots = vec![vault_path.to_path_buf()];
+
+    // 2. Explicitly check for an 'Archive' folder that might be a sibling
+    // (In case your CLI path points to 'Tasks' but archive is at 'Archive')
+    if !options.no_sibling_archive {
+        if let Some(parent) = vault_path.parent() {
+            let archive_sibling = parent.join("Archive");
+            if archive_sibling.exists() && archive_sibling != vault_path {
+                roots.push(archive_sibling);
+            }
+        }
+    }
+
+    // 3. Any extra archive folders the user pointed us at
+    roots.extend(options.archives.iter().cloned());
+    roots
+}
+
+/// The folders scanned for several vault paths: the [`scan_roots`] of each,
+/// with folders reached from more than one path (e.g. a shared `Archive`)
+/// listed once
+pub fn scan_roots_all(vault_paths: &[PathBuf], options: &ScanOptions) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    vault_paths
+        .iter()
+        .flat_map(|path| scan_roots(path, options))
+        .filter(|root| seen.insert(root.clone()))
+        .collect()
+}
+
+/// Expand `*`, `?` and `**` in a vault path to the folders it matches, in
+/// sorted order. A path without wildcards is returned as it is.
+pub fn expand_path_glob(pattern: &Path) -> Vec<PathBuf> {
+    let is_glob = |s: &str| s.contains(['*', '?']);
+    let components: Vec<String> = pattern
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let Some(split) = components.iter().position(|c| is_glob(c)) else {
+        return vec![pattern.to_path_buf()];
+    };
+
+    // Walk from the deepest folder that is named without wildcards
+    let base: PathBuf = pattern.components().take(split).collect();
+    let base = if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    };
+    let rest = components[split..].join("/");
+    let max_depth = if rest.contains("**") {
+        MAX_WALK_DEPTH
+    } else {
+        components.len() - split
+    };
+    let mut matches: Vec<PathBuf> = WalkDir::new(&base)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter(|e| {
+            e.path().strip_prefix(&base).is_ok_and(|relative| {
+                let relative: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                glob_match(&rest, &relative.join("/"))
+            })
+        })
+        .map(|e| e.into_path())
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Collect all tasks under `vault_path` with the default [`ScanOptions`]
+pub fn collect_tasks(vault_path: &Path) -> Result<Vec<Task>> {
+    collect_tasks_with(vault_path, &ScanOptions::default())
+}
+
+/// Collect all tasks under `vault_path`. Files that fail to parse are reported
+/// on stderr (unless `quiet`), or turned into a single error listing all of
+/// them when `strict`.
+pub fn collect_tasks_with(vault_path: &Path, options: &ScanOptions) -> Result<Vec<Task>> {
+    collect_tasks_from(&[vault_path.to_path_buf()], options)
+}
+
+/// Collect the tasks of several vaults as one list, deduplicated across all of
+/// them and ordered by filename. Each path gets its own `Archive` sibling
+/// check, as in [`scan_roots`].
+pub fn collect_tasks_from(vault_paths: &[PathBuf], options: &ScanOptions) -> Result<Vec<Task>> {
+    collect_tasks_reporting(vault_paths, options).map(|(tasks, _)| tasks)
+}
+
+/// Like [`collect_tasks_from`], but also returns a [`ScanWarning`] for every
+/// file that was skipped, whether or not it was reported on stderr
+pub fn collect_tasks_reporting(
+    vault_paths: &[PathBuf],
+    options: &ScanOptions,
+) -> Result<(Vec<Task>, Vec<ScanWarning>)> {
+    let mut tasks = Vec::new();
+    let mut failures = Vec::new();
+
+    let mut cache = options.cache.as_deref().map(TaskCache::load);
+    for root in scan_roots_all(vault_paths, options) {
+        let scanned = tasks.len();
+        scan_dir(&root, options, cache.as_mut(), &mut tasks, &mut failures);
+        let root_is_archive = !vault_paths.contains(&root);
+        for task in &mut tasks[scanned..] {
+            task.source = SourceKind::of(&root, &task.path, root_is_archive);
+        }
+    }
+    if let Some(Err(e)) = cache.map(TaskCache::save) {
+        eprintln!("warning: {:#}", e);
+    }
+
+    let decisions = dedup_tasks(&mut tasks, options.dedup);
+    // Walk order differs between filesystems and runs; keep output reproducible
+    tasks.sort_by(|a, b| {
+        a.filename
+            .cmp(&b.filename)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    if options.verbose {
+        let same = match options.dedup {
+            DedupKey::FilenameCreated => "filename and dateCreated",
+            DedupKey::Path | DedupKey::None => "file",
+        };
+        for decision in &decisions {
+            eprintln!(
+                "dedup: dropped {}, same {} as {}",
+                decision.dropped.display(),
+                same,
+                decision.kept.display()
+            );
+        }
+    }
+
+    // Unreadable files are transient (a sync mid-write, a deleted note), so
+    // only parse errors fail a --strict scan
+    let (io_failures, failures): (Vec<_>, Vec<_>) =
+        failures.into_iter().partition(ParseFailure::is_io);
+    for failure in io_failures.iter().filter(|_| !options.quiet) {
+        eprintln!(
+            "warning: could not read {}, it may have changed during the scan: {}",
+            failure.path.display(),
+            failure.error.root_cause()
+        );
+    }
+
+    if options.strict && !failures.is_empty() {
+        let report: Vec<String> = failures
+            .iter()
+            .map(|f| format!("  {}: {:#}", f.path.display(), f.error))
+            .collect();
+        anyhow::bail!(
+            "{} file(s) failed to parse:\n{}",
+            failures.len(),
+            report.join("\n")
+        );
+    }
+
+    for failure in failures.iter().filter(|_| !options.quiet) {
+        eprintln!(
+            "warning: skipping {}: {:#}",
+            failure.path.display(),
+            failure.error
+        );
+    }
+
+    let warnings = io_failures
+        .iter()
+        .map(|f| ScanWarning {
+            path: f.path.clone(),
+            reason: format!("could not read the file: {}", f.error.root_cause()),
+        })
+        .chain(failures.iter().map(|f| ScanWarning {
+            path: f.path.clone(),
+            reason: format!("{:#}", f.error),
+        }))
+        .collect();
+    Ok((tasks, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(frontmatter: &str) -> Task {
+        parse_task_str(&format!("---\n{}\n---\n", frontmatter), "note").unwrap()
+    }
+
+    #[test]
+    fn list_fields_accept_a_single_string() {
+        let t =
+            task("status: open\ntags: work, urgent\nprojects: \"[[A]], B\"\ndependsOn: \"[[x]]\"");
+        assert_eq!(t.tags, ["work", "urgent"]);
+        assert_eq!(t.projects, ["[[A]]", "B"]);
+        assert_eq!(t.depends_on, ["[[x]]"]);
+
+        let t = task("status: open\ntags: errand home\ndepends_on:\n  - a\n  - b");
+        assert_eq!(t.tags, ["errand", "home"]);
+        assert_eq!(t.depends_on, ["a", "b"]);
+    }
+
+    /// The fixed day the date-relative tests are judged at
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    fn noon() -> NaiveDateTime {
+        today().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn due_in(days: i64, status: &str) -> Task {
+        let due = today() + chrono::Duration::days(days);
+        task(&format!("status: {}\ndue: {}", status, due))
+    }
+
+    #[test]
+    fn due_window_runs_from_tomorrow_to_exactly_n_days_out() {
+        assert!(!due_in(0, "open").is_due_within(7, today()));
+        assert!(due_in(1, "open").is_due_within(7, today()));
+        assert!(due_in(7, "open").is_due_within(7, today()));
+        assert!(!due_in(8, "open").is_due_within(7, today()));
+        assert!(!due_in(-1, "open").is_due_within(7, today()));
+        assert!(!due_in(1, "open").is_due_within(0, today()));
+    }
+
+    #[test]
+    fn due_window_skips_undated_and_done_tasks() {
+        assert!(!task("status: open").is_due_within(7, today()));
+        assert!(!due_in(3, "done").is_due_within(7, today()));
+    }
+
+    fn split(content: &str) -> (FrontmatterFormat, String, String) {
+        let (frontmatter, body) = split_frontmatter(content).expect("frontmatter");
+        (frontmatter.format, frontmatter.text, body)
+    }
+
+    #[test]
+    fn frontmatter_after_a_byte_order_mark() {
+        let (format, text, body) = split("\u{feff}---\nstatus: open\n---\nbody");
+        assert_eq!(format, FrontmatterFormat::Yaml);
+        assert_eq!(text, "status: open");
+        assert_eq!(body, "body");
+        assert_eq!(task("status: open").status, "open");
+        assert!(parse_task_str("\u{feff}---\nstatus: open\n---\n", "bom").is_ok());
+    }
+
+    #[test]
+    fn frontmatter_with_carriage_returns() {
+        let (_, text, body) = split("---\r\nstatus: open\r\ndue: 2026-10-14\r\n---\r\nbody\r\n");
+        assert_eq!(text, "status: open\ndue: 2026-10-14");
+        assert_eq!(body, "body");
+
+        let (_, text, body) = split("---\rstatus: open\r---\rbody");
+        assert_eq!(text, "status: open");
+        assert_eq!(body, "body");
+    }
+
+    #[test]
+    fn frontmatter_after_leading_blank_lines() {
+        let (_, text, _) = split("\n  \n---\nstatus: open\n---\n");
+        assert_eq!(text, "status: open");
+        assert!(split_frontmatter("text\n---\nstatus: open\n---\n").is_none());
+    }
+
+    #[test]
+    fn toml_frontmatter_needs_a_matching_delimiter() {
+        let (format, text, _) = split("+++\nstatus = \"open\"\n+++\nbody");
+        assert_eq!(format, FrontmatterFormat::Toml);
+        assert_eq!(text, "status = \"open\"");
+        assert!(split_frontmatter("+++\nstatus = \"open\"\n---\n").is_none());
+        assert!(split_frontmatter("---\nstatus: open\n").is_none());
+    }
+
+    #[test]
+    fn toml_notes_are_read_end_to_end() {
+        let dir = crate::testutil::TempDir::new();
+        dir.write(
+            "toml.md",
+            concat!(
+                "+++\n",
+                "title = \"Ship it\" # comment\n",
+                "status = \"open\"\n",
+                "priority = 'high'\n",
+                "tags = [\"work\", \"release\"]\n",
+                "due = 2026-01-02\n",
+                "scheduled = 2026-01-01T09:30:00\n",
+                "timeEstimate = 45\n",
+                "[extra]\n",
+                "status = \"ignored\"\n",
+                "+++\n",
+                "body\n",
+            ),
+        );
+        let tasks = scan(&[dir.path().to_path_buf()], ScanOptions::default());
+        let [t] = tasks.as_slice() else {
+            panic!("{:?}", tasks)
+        };
+        assert_eq!(t.title.as_deref(), Some("Ship it"));
+        assert_eq!(t.status, "open");
+        assert_eq!(t.priority.as_deref(), Some("high"));
+        assert_eq!(t.tags, ["work", "release"]);
+        assert_eq!(t.due.unwrap().to_string(), "2026-01-02");
+        assert_eq!(t.scheduled.unwrap().to_string(), "2026-01-01T09:30:00");
+    }
+
+    /// A vault of `count` notes where every fifth one has no frontmatter
+    fn vault(count: usize) -> (crate::testutil::TempDir, Vec<PathBuf>) {
+        let dir = crate::testutil::TempDir::new();
+        let paths = (0..count)
+            .map(|i| {
+                let content = if i % 5 == 4 {
+                    format!("no frontmatter in note {}", i)
+                } else {
+                    format!(
+                        "---\ntitle: Task {}\npriority: high\ndue: 2026-10-{:02}\n---\n",
+                        i,
+                        i % 28 + 1
+                    )
+                };
+                dir.write(&format!("folder-{}/task-{}.md", i % 3, i), &content)
+            })
+            .collect();
+        (dir, paths)
+    }
+
+    fn outcomes(results: Vec<Result<Task>>) -> Vec<String> {
+        results
+            .into_iter()
+            .map(|r| match r {
+                Ok(task) => format!("{:?}", task),
+                Err(error) => format!("error: {:#}", error),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn thread_count_does_not_change_parse_results() {
+        let (_dir, paths) = vault(37);
+        let sequential = outcomes(parse_task_files(&paths, 1, Some("open")));
+        assert_eq!(
+            sequential.iter().filter(|o| o.starts_with("error")).count(),
+            7
+        );
+        for threads in [0, 2, 4, 8, 100] {
+            assert_eq!(
+                outcomes(parse_task_files(&paths, threads, Some("open"))),
+                sequential,
+                "{} threads",
+                threads
+            );
+        }
+    }
+
+    #[test]
+    fn thread_count_does_not_change_collected_tasks() {
+        let (dir, _paths) = vault(37);
+        let roots = [dir.path().to_path_buf()];
+        let collect = |threads| {
+            let options = ScanOptions {
+                threads: Some(threads),
+                default_status: Some("open".to_string()),
+                quiet: true,
+                ..ScanOptions::default()
+            };
+            let tasks = collect_tasks_from(&roots, &options).unwrap();
+            tasks.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>()
+        };
+        let sequential = collect(1);
+        assert_eq!(sequential.len(), 30);
+        assert_eq!(collect(4), sequential);
+        assert_eq!(collect(16), sequential);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_end_the_walk() {
+        use std::os::unix::fs::symlink;
+
+        let dir = crate::testutil::TempDir::new();
+        let note = dir.write("area/task.md", "---\nstatus: open\n---\n");
+        symlink(dir.path(), dir.path().join("area/back-to-root")).unwrap();
+        symlink(dir.path().join("area"), dir.path().join("area/itself")).unwrap();
+
+        for follow_links in [false, true] {
+            let options = ScanOptions {
+                follow_links,
+                quiet: true,
+                ..ScanOptions::default()
+            };
+            assert_eq!(
+                task_files(dir.path(), &options),
+                std::slice::from_ref(&note)
+            );
+
+            let (mut tasks, mut failures) = (Vec::new(), Vec::new());
+            scan_dir(dir.path(), &options, None, &mut tasks, &mut failures);
+            assert_eq!(tasks.len(), 1);
+            assert!(failures.is_empty());
+        }
+    }
+
+    const OPEN: &str = "---\nstatus: open\n---\n";
+
+    fn filenames(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.filename.as_str()).collect()
+    }
+
+    fn scan(roots: &[PathBuf], options: ScanOptions) -> Vec<Task> {
+        let options = ScanOptions {
+            quiet: true,
+            ..options
+        };
+        collect_tasks_from(roots, &options).unwrap()
+    }
+
+    #[test]
+    fn excluded_folders_contribute_no_tasks() {
+        let dir = crate::testutil::TempDir::new();
+        dir.write("Tasks/keep.md", OPEN);
+        dir.write("Tasks/Templates/template.md", OPEN);
+        dir.write("Tasks/Templates/nested/deeper.md", OPEN);
+        dir.write("Tasks/area/drafts/draft.md", OPEN);
+        dir.write("Tasks/area/also-kept.md", OPEN);
+        let roots = [dir.path().join("Tasks")];
+
+        let exclude = |patterns: &[&str]| ScanOptions {
+            exclude: patterns.iter().map(|p| p.to_string()).collect(),
+            ..ScanOptions::default()
+        };
+        assert_eq!(scan(&roots, ScanOptions::default()).len(), 5);
+        assert_eq!(
+            filenames(&scan(&roots, exclude(&["Templates", "**/drafts"]))),
+            ["also-kept", "keep"]
+        );
+        assert_eq!(
+            filenames(&scan(&roots, exclude(&["./Templates/", "area/*.md"]))),
+            ["draft", "keep"]
+        );
+        // Matched against the path below the scanned folder, not the full path
+        assert_eq!(scan(&roots, exclude(&["Tasks"])).len(), 5);
+    }
+
+    #[test]
+    fn exclude_applies_to_a_nested_archive_but_not_the_sibling() {
+        let dir = crate::testutil::TempDir::new();
+        dir.write("Tasks/keep.md", OPEN);
+        dir.write("Tasks/Archive/nested.md", OPEN);
+        dir.write("Archive/sibling.md", OPEN);
+        let roots = [dir.path().join("Tasks")];
+
+        let options = ScanOptions {
+            exclude: vec!["Archive".to_string()],
+            ..ScanOptions::default()
+        };
+        assert_eq!(filenames(&scan(&roots, options)), ["keep", "sibling"]);
+        let options = ScanOptions {
+            exclude: vec!["Archive".to_string()],
+            no_sibling_archive: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(filenames(&scan(&roots, options)), ["keep"]);
+    }
+
+    fn due_at(offset: chrono::Duration) -> Task {
+        let due = noon() + offset;
+        task(&format!(
+            "status: open\ndue: {}",
+            due.format("%Y-%m-%dT%H:%M:%S")
+        ))
+    }
+
+    #[test]
+    fn timed_due_dates_are_overdue_once_their_time_passes() {
+        assert!(due_at(-chrono::Duration::minutes(1)).is_overdue(noon()));
+        assert!(!due_at(chrono::Duration::hours(1)).is_overdue(noon()));
+        assert!(!due_at(chrono::Duration::zero()).is_overdue(noon()));
+        assert!(due_at(chrono::Duration::hours(-1))
+            .due
+            .unwrap()
+            .time()
+            .is_some());
+    }
+
+    #[test]
+    fn date_only_due_dates_are_overdue_from_the_next_day() {
+        let on = |days: i64| {
+            task(&format!(
+                "status: open\ndue: {}",
+                today() + chrono::Duration::days(days)
+            ))
+        };
+        assert!(!on(0).is_overdue(noon()));
+        assert!(on(0).is_due_today(today()));
+        assert!(on(-1).is_overdue(noon()));
+        assert!(!on(-1).is_due_today(today()));
+        assert!(!on(1).is_overdue(noon()));
+        assert!(!on(1).is_due_today(today()));
+    }
+
+    #[test]
+    fn archive_folders_are_scanned_and_deduplicated_against_the_main_folder() {
+        let dir = crate::testutil::TempDir::new();
+        let created = "---\nstatus: done\ndateCreated: 2026-01-30T08:00:00\n---\n";
+        dir.write("Tasks/active.md", OPEN);
+        dir.write("Tasks/moved.md", created);
+        dir.write("Completed/moved.md", created);
+        dir.write("Completed/old.md", created);
+        dir.write("Archive/sibling.md", OPEN);
+        let roots = [dir.path().join("Tasks")];
+        let archive = dir.path().join("Completed");
+
+        let tasks = scan(
+            &roots,
+            ScanOptions {
+                archives: vec![archive.clone()],
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(filenames(&tasks), ["active", "moved", "old", "sibling"]);
+        assert_eq!(tasks[1].path, dir.path().join("Tasks/moved.md"));
+        assert_eq!(tasks[1].source, SourceKind::Primary);
+        assert_eq!(tasks[2].source, SourceKind::Archive);
+        assert_eq!(tasks[3].source, SourceKind::Archive);
+
+        let tasks = scan(
+            &roots,
+            ScanOptions {
+                archives: vec![archive],
+                no_sibling_archive: true,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(filenames(&tasks), ["active", "moved", "old"]);
+        assert_eq!(
+            filenames(&scan(&roots, ScanOptions::default())),
+            ["active", "moved", "sibling"]
+        );
+    }
+
+    #[test]
+    fn copies_in_main_and_archive_folders_are_kept_once() {
+        let dir = crate::testutil::TempDir::new();
+        let created = |date: &str| format!("---\nstatus: open\ndateCreated: {}\n---\n", date);
+        for folder in ["Tasks", "Tasks/Archive", "Archive"] {
+            dir.write(
+                &format!("{}/copied.md", folder),
+                &created("2026-01-30T08:00:00"),
+            );
+        }
+        dir.write("Tasks/renewed.md", &created("2026-02-01T08:00:00"));
+        dir.write("Archive/renewed.md", &created("2026-01-01T08:00:00"));
+        dir.write("Tasks/undated.md", OPEN);
+        dir.write("Archive/undated.md", OPEN);
+        let roots = [dir.path().join("Tasks")];
+
+        let tasks = scan(&roots, ScanOptions::default());
+        assert_eq!(
+            filenames(&tasks),
+            ["copied", "renewed", "renewed", "undated"]
+        );
+        assert_eq!(tasks[0].path, dir.path().join("Tasks/copied.md"));
+        assert_eq!(tasks[3].path, dir.path().join("Tasks/undated.md"));
+        // Same filename but a different dateCreated is a different task
+        let created: Vec<_> = tasks[1..3]
+            .iter()
+            .map(|t| t.date_created.unwrap().to_string())
+            .collect();
+        assert_eq!(created.len(), 2);
+        assert_ne!(created[0], created[1]);
+
+        let all = scan(
+            &roots,
+            ScanOptions {
+                dedup: DedupKey::None,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(all.len(), 7);
+    }
+
+    #[test]
+    fn deduplication_keeps_one_order_whatever_the_input_order() {
+        let dir = crate::testutil::TempDir::new();
+        let note = "---\nstatus: open\ndateCreated: 2026-01-30T08:00:00\n---\n";
+        let primary = dir.write("Tasks/task.md", note);
+        dir.write("Archive/task.md", note);
+        dir.write("Tasks/Archive/task.md", note);
+        let mut tasks = scan(
+            &[dir.path().join("Tasks")],
+            ScanOptions {
+                dedup: DedupKey::None,
+                ..ScanOptions::default()
+            },
+        );
+        for _ in 0..tasks.len() {
+            tasks.rotate_left(1);
+            let mut copies = tasks.clone();
+            let dropped = dedup_tasks(&mut copies, DedupKey::FilenameCreated);
+            assert_eq!(copies.len(), 1);
+            assert_eq!(copies[0].path, primary);
+            assert_eq!(dropped.len(), 2);
+            assert!(dropped.iter().all(|d| d.dropped != primary));
+        }
+    }
+
+    #[test]
+    fn crlf_notes_parse_without_stray_carriage_returns() {
+        let note = "---\r\ntitle: Call the bank\r\nstatus: open\r\ntags:\r\n  - errand\r\n  - phone\r\ndue: 2026-10-20\r\n---\r\nNotes\r\n";
+        let task = parse_task_str(note, "crlf").unwrap();
+        assert_eq!(task.title.as_deref(), Some("Call the bank"));
+        assert_eq!(task.status, "open");
+        assert_eq!(task.tags, ["errand", "phone"]);
+        assert_eq!(task.due.unwrap().to_string(), "2026-10-20");
+        assert_eq!(task.body, "Notes");
+        assert_eq!(task.body_text(), "Notes");
+    }
+
+    #[test]
+    fn completed_ranges_are_inclusive_and_may_be_open_ended() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d);
+        let done = task("status: done\ncompletedDate: 2024-06-05");
+        assert!(done.completed_between(date(5), date(5)));
+        assert!(done.completed_between(date(1), date(7)));
+        assert!(!done.completed_between(date(6), date(7)));
+        assert!(!done.completed_between(date(1), date(4)));
+        assert!(done.completed_between(None, date(5)));
+        assert!(done.completed_between(date(5), None));
+        assert!(!done.completed_between(date(6), None));
+        assert!(done.completed_between(None, None));
+        assert!(!task("status: done").completed_between(None, None));
+    }
+
+    #[test]
+    fn nested_blocks_under_unknown_keys_are_ignored() {
+        let task = task(concat!(
+            "title: Renew passport\n",
+            "status: open\n",
+            "metadata:\n",
+            "  source: import\n",
+            "  history:\n",
+            "    - { at: 2026-01-01, by: me }\n",
+            "    - [nested, list]\n",
+            "plugin:\n",
+            "  settings: { deep: { deeper: true } }\n",
+            "priority: high\n",
+            "due: 2026-11-01"
+        ));
+        assert_eq!(task.title.as_deref(), Some("Renew passport"));
+        assert_eq!(task.priority.as_deref(), Some("high"));
+        assert_eq!(task.due.unwrap().to_string(), "2026-11-01");
+    }
+
+    #[test]
+    fn only_the_first_yaml_document_is_read() {
+        let task =
+            parse_frontmatter_yaml("status: open\ntitle: First\n---\nstatus: done\ntitle: Second")
+                .unwrap();
+        assert_eq!(task.title.as_deref(), Some("First"));
+        assert!(task.is_open());
+    }
+
+    #[test]
+    fn due_ranges_are_inclusive_and_may_be_open_ended() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d);
+        let due = task("status: open\ndue: 2024-06-10T15:00");
+        assert!(due.due_in_range(None, date(10)));
+        assert!(!due.due_in_range(None, date(9)));
+        assert!(due.due_in_range(date(10), None));
+        assert!(!due.due_in_range(date(11), None));
+        assert!(due.due_in_range(date(1), date(30)));
+        assert!(due.due_in_range(date(10), date(10)));
+        assert!(!due.due_in_range(date(11), date(30)));
+        assert!(!task("status: open").due_in_range(None, date(30)));
+        assert!(!task("status: open").due_in_range(date(1), None));
+    }
+
+    #[test]
+    fn contexts_match_with_or_without_the_at_sign() {
+        let wanted = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let at_home = task("status: open\ncontexts:\n  - \"@home\"\n  - Computer");
+        assert!(at_home.has_context(&wanted(&["home"]), CaseMode::Insensitive));
+        assert!(at_home.has_context(&wanted(&["@home"]), CaseMode::Insensitive));
+        assert!(at_home.has_context(&wanted(&[" @computer "]), CaseMode::Insensitive));
+        assert!(at_home.has_context(&wanted(&["phone", "home"]), CaseMode::Insensitive));
+        assert!(!at_home.has_context(&wanted(&["@phone"]), CaseMode::Insensitive));
+        assert!(!at_home.has_context(&wanted(&["@computer"]), CaseMode::Sensitive));
+        assert!(at_home.has_context(&[], CaseMode::Insensitive));
+
+        let none = task("status: open");
+        assert!(none.has_context(&[], CaseMode::Insensitive));
+        assert!(!none.has_context(&wanted(&["home"]), CaseMode::Insensitive));
+    }
+
+    #[test]
+    fn source_types_match_ignoring_case() {
+        let tasks = [
+            task("status: open\ntaskSourceType: inline"),
+            task("status: open\ntaskSourceType: Note"),
+            task("status: open\ntaskSourceType: \" INLINE \""),
+            task("status: open"),
+        ];
+        let matching = |wanted: &str, case: CaseMode| {
+            tasks
+                .iter()
+                .map(|t| t.has_source_type(wanted, case))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            matching("Inline", CaseMode::Insensitive),
+            [true, false, true, false]
+        );
+        assert_eq!(
+            matching("note", CaseMode::Insensitive),
+            [false, true, false, false]
+        );
+        assert_eq!(
+            matching("inline", CaseMode::Sensitive),
+            [true, false, false, false]
+        );
+        assert_eq!(
+            matching("", CaseMode::Insensitive),
+            [false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn settled_notes_are_read_once_even_when_empty() {
+        let dir = crate::testutil::TempDir::new();
+        let empty = dir.write("empty.md", "");
+        let note = dir.write("note.md", OPEN);
+        let started = std::time::Instant::now();
+        assert_eq!(read_note(&empty).unwrap(), "");
+        assert_eq!(read_note(&note).unwrap(), OPEN);
+        assert!(started.elapsed() < READ_RETRY_DELAY);
+        assert!(read_note(&dir.path().join("missing.md")).is_err());
+    }
+
+    /// Set the modification time of `path`, as `touch -d` would
+    fn touch(path: &Path, time: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|f| f.set_modified(time))
+            .unwrap();
+    }
+
+    #[test]
+    fn modified_since_uses_the_file_mtime() {
+        let dir = crate::testutil::TempDir::new();
+        let old = dir.write("old.md", OPEN);
+        let recent = dir.write("recent.md", OPEN);
+        dir.write("fresh.md", OPEN);
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        touch(&old, SystemTime::UNIX_EPOCH + 18_000 * day);
+        touch(&recent, SystemTime::now() - 2 * day);
+
+        let tasks = scan(&[dir.path().to_path_buf()], ScanOptions::default());
+        let since = |threshold: &str| {
+            let now = clock::now(clock::timezone());
+            let threshold = threshold.parse::<clock::Since>().unwrap().resolve(now);
+            tasks
+                .iter()
+                .filter(|t| t.modified_since(threshold))
+                .map(|t| t.filename.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(since("2000-01-01"), ["fresh", "old", "recent"]);
+        assert_eq!(since("2020-01-01"), ["fresh", "recent"]);
+        assert_eq!(since("3d"), ["fresh", "recent"]);
+        assert_eq!(since("1d"), ["fresh"]);
+        let epoch = clock::Since::Date(NaiveDate::MIN).resolve(noon());
+        assert!(!task("status: open").modified_since(epoch));
+    }
+
+    #[test]
+    fn this_week_runs_from_the_week_start_to_the_day_before_the_next() {
+        use chrono::Datelike;
+
+        let due_on = |date: NaiveDate| task(&format!("status: open\ndue: {}", date));
+        for week_start in [Weekday::Mon, Weekday::Sun] {
+            let first = today().week(week_start).first_day();
+            let last = today().week(week_start).last_day();
+            assert_eq!(first.weekday(), week_start);
+            assert_eq!((last - first).num_days(), 6);
+            for day in [first, today(), last] {
+                assert!(
+                    due_on(day).is_due_this_week(week_start, today()),
+                    "{} {}",
+                    week_start,
+                    day
+                );
+            }
+            for day in [first.pred_opt().unwrap(), last.succ_opt().unwrap()] {
+                assert!(
+                    !due_on(day).is_due_this_week(week_start, today()),
+                    "{} {}",
+                    week_start,
+                    day
+                );
+            }
+        }
+        assert!(!task("status: open").is_due_this_week(Weekday::Mon, today()));
+        let done = task(&format!("status: done\ndue: {}", today()));
+        assert!(!done.is_due_this_week(Weekday::Mon, today()));
+    }
+
+    #[test]
+    fn copies_prefer_primary_then_completed_then_modified_then_path() {
+        let copy = |path: &str, source, completed: Option<&str>, modified: u64| {
+            let completed = completed.map_or(String::new(), |c| format!("\ncompletedDate: {}", c));
+            let mut task = task(&format!("status: done{}", completed));
+            task.path = PathBuf::from(path);
+            task.source = source;
+            task.modified = Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified));
+            task
+        };
+        let archived_later = copy("b/Archive/t.md", SourceKind::Archive, Some("2026-02-01"), 9);
+        let primary = copy("c/t.md", SourceKind::Primary, Some("2026-01-01"), 1);
+        assert!(cmp_copies(&primary, &archived_later).is_gt());
+
+        let earlier = copy("a/t.md", SourceKind::Primary, Some("2026-01-01"), 9);
+        let later = copy("b/t.md", SourceKind::Primary, Some("2026-02-01"), 1);
+        assert!(cmp_copies(&later, &earlier).is_gt());
+        let uncompleted = copy("a/t.md", SourceKind::Primary, None, 9);
+        assert!(cmp_copies(&earlier, &uncompleted).is_gt());
+
+        let stale = copy("a/t.md", SourceKind::Primary, None, 1);
+        let touched = copy("b/t.md", SourceKind::Primary, None, 2);
+        assert!(cmp_copies(&touched, &stale).is_gt());
+
+        let first = copy("a/t.md", SourceKind::Archive, None, 1);
+        let second = copy("b/t.md", SourceKind::Archive, None, 1);
+        assert!(cmp_copies(&first, &second).is_gt());
+        assert!(cmp_copies(&first, &first).is_eq());
+    }
+
+    #[test]
+    fn tasks_come_back_ordered_by_filename_then_path() {
+        let dir = crate::testutil::TempDir::new();
+        for note in [
+            "z/alpha.md",
+            "a/beta.md",
+            "m/alpha.md",
+            "b/Gamma.md",
+            "a/alpha.md",
+        ] {
+            dir.write(note, OPEN);
+        }
+        let roots = [dir.path().to_path_buf()];
+        let options = ScanOptions {
+            dedup: DedupKey::Path,
+            ..ScanOptions::default()
+        };
+        let tasks = scan(&roots, options.clone());
+        let relative: Vec<_> = tasks
+            .iter()
+            .map(|t| {
+                t.path
+                    .strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(
+            relative,
+            [
+                "b/Gamma.md",
+                "a/alpha.md",
+                "m/alpha.md",
+                "z/alpha.md",
+                "a/beta.md"
+            ]
+        );
+
+        let json = |tasks: &[Task]| serde_json::to_string(tasks).unwrap();
+        let first = json(&tasks);
+        for threads in [1, 2, 8] {
+            let options = ScanOptions {
+                threads: Some(threads),
+                ..options.clone()
+            };
+            assert_eq!(json(&scan(&roots, options)), first);
+        }
+    }
+
+    #[test]
+    fn notes_from_stdin_honor_the_scan_options() {
+        let options = ScanOptions {
+            default_status: Some("archived".to_string()),
+            statuses: Statuses::with_extra(&["archived".to_string()], &[]),
+            ..ScanOptions::default()
+        };
+        let task = parse_task_str_with("---\ntitle: Piped\n---\n", "stdin", &options).unwrap();
+        assert_eq!(task.status, "archived");
+        assert!(task.is_done());
+        let task = parse_task_str_with("---\nstatus: open\n---\n", "stdin", &options).unwrap();
+        assert_eq!(task.status, "open");
+        assert!(parse_task_str_with("---\n---\n", "stdin", &ScanOptions::default()).is_err());
+    }
+}
@@ -1,18 +1,304 @@
 use anyhow::{Context, Result};
-use chrono::{Local, NaiveDate};
-use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use obsidian_tasks::calendar::render_month;
+use obsidian_tasks::clock::{self, Since};
+use obsidian_tasks::config::{load_config, load_config_from, Config, CONFIG_FILE_NAME};
+use obsidian_tasks::doctor::Diagnosis;
+use obsidian_tasks::ics::render_ics;
+use obsidian_tasks::note::{plan_completion, NewTask, NoteWrite, WriteKind};
+use obsidian_tasks::output;
+use obsidian_tasks::pick;
+use obsidian_tasks::priority;
+use obsidian_tasks::regex::Regex;
+use obsidian_tasks::render::{
+    format_minutes, group_by_project, group_tasks, json_lines, render_envelope, render_groups,
+    render_tasks, sort_tasks, to_json, ColorChoice, GroupKey, Listing, OutputFormat, RenderOptions,
+    SortKey, TaskJson,
+};
+use obsidian_tasks::schema::{field_names, task_schema_for};
+use obsidian_tasks::stats::Stats;
+use obsidian_tasks::status;
+use obsidian_tasks::tz::Tz;
+use obsidian_tasks::validate::{render_issues_plain, validate_vault, Severity};
+use obsidian_tasks::vault;
+use obsidian_tasks::watch;
+use obsidian_tasks::waybar::{WaybarClasses, WaybarOutput};
+use obsidian_tasks::{
+    cmp_urgency, collect_tasks_from, collect_tasks_reporting, dependency_name, expand_path_glob,
+    most_urgent, parse_task_str_with, total_effort, CaseMode, DedupKey, Dependencies, ScanOptions,
+    ScanWarning, Statuses, Task,
+};
+use std::io::Read;
+use std::path::PathBuf;
+
+/// `println!` to stdout or the `--output` file
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        output::write_line(&format!($($arg)*))?
+    };
+}
+
+/// `print!` to stdout or the `--output` file
+macro_rules! out {
+    ($($arg:tt)*) => {
+        output::write(&format!($($arg)*))?
+    };
+}
 
 #[derive(Parser)]
 #[command(name = "obsidian-tasks")]
 #[command(about = "Parse and filter tasks from Obsidian TaskNotes", long_about = None)]
 struct Cli {
-    /// Path to your Obsidian vault's TaskNotes folder
+    /// Path to your Obsidian vault's TaskNotes folder (defaults to `path` from the config file;
+    /// not needed with --stdin). Repeat it to scan several vaults at once; `*`, `?` and `**`
+    /// wildcards match folders
     #[arg(short, long)]
-    path: PathBuf,
+    path: Vec<PathBuf>,
+
+    /// Vault root folder: the TaskNotes folders are read from the plugin's settings in
+    /// `.obsidian/plugins/tasknotes/data.json`, or the whole vault is scanned without them
+    #[arg(long, value_name = "DIR", conflicts_with = "path")]
+    vault_root: Option<PathBuf>,
+
+    /// Read a single note from stdin instead of scanning --path
+    #[arg(long, conflicts_with_all = ["path", "vault_root"])]
+    stdin: bool,
+
+    /// Keep running and print the result again whenever a task note changes
+    #[arg(long, conflicts_with = "stdin")]
+    watch: bool,
+
+    /// Config file to use instead of searching ./crustsidian.toml and $XDG_CONFIG_HOME
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Output format for list commands [default: json]
+    #[arg(short, long, value_enum, global = true)]
+    format: Option<OutputFormat>,
+
+    /// Write results to this file instead of stdout, creating parent folders as needed.
+    /// With --watch the file is rewritten on every refresh
+    #[arg(short, long, global = true)]
+    output: Option<PathBuf>,
+
+    /// Vault name used in the `obsidian_uri` computed field [default: the folder holding
+    /// `.obsidian` above each note]
+    #[arg(long, global = true)]
+    vault_name: Option<String>,
+
+    /// Evaluate today, overdue and other date filters as of this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", global = true)]
+    as_of: Option<NaiveDate>,
+
+    /// IANA timezone (e.g. `Europe/Berlin`) used to decide what "today" is [default: system zone]
+    #[arg(long, global = true)]
+    timezone: Option<Tz>,
+
+    /// When to color plain and table output by urgency
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    color: ColorChoice,
+
+    /// Print JSON output on a single line instead of pretty-printed
+    #[arg(long, global = true)]
+    compact: bool,
+
+    /// Add computed fields to JSON output: `days_until_due` (negative when overdue)
+    #[arg(long, global = true)]
+    with_computed: bool,
+
+    /// Only include these fields in JSON output, in this order (comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    fields: Vec<String>,
+
+    /// Add each note's body (the markdown after the frontmatter) to JSON output
+    #[arg(long, global = true)]
+    include_body: bool,
+
+    /// Print statuses as done, cancelled, in-progress or open, however the notes spell them
+    #[arg(long, global = true)]
+    normalize_status: bool,
+
+    /// Show due dates in plain and table output as "in 2 days" or "3 days overdue"
+    #[arg(long, global = true)]
+    relative_dates: bool,
+
+    /// Fail with a list of every file that could not be parsed instead of warning
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Don't warn about folders that can't be read or notes that fail to parse
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Explain scan decisions on stderr, such as which copy of a duplicated task was kept
+    #[arg(short, long, conflicts_with = "quiet", global = true)]
+    verbose: bool,
+
+    /// Number of threads used to parse task files (defaults to all cores)
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
+    /// Skip files and folders matching this path or glob, relative to the scanned folder (repeatable)
+    #[arg(long, global = true)]
+    exclude: Vec<String>,
+
+    /// Read notes whose frontmatter has no status (or is empty) as tasks with this
+    /// status, e.g. `open`, instead of skipping them
+    #[arg(long, value_name = "STATUS", global = true)]
+    default_status: Option<String>,
+
+    /// File extension scanned as a note, e.g. `markdown` (repeatable or comma-separated)
+    /// [default: md]
+    #[arg(long, value_name = "EXT", value_delimiter = ',', global = true)]
+    ext: Vec<String>,
+
+    /// Extra archive folder to scan and deduplicate against the main path (repeatable)
+    #[arg(long, global = true)]
+    archive: Vec<PathBuf>,
+
+    /// Don't automatically scan an `Archive` folder next to --path
+    #[arg(long, global = true)]
+    no_archive: bool,
+
+    /// How to recognize a task found more than once (see README for the tradeoff)
+    #[arg(long, value_enum, default_value_t = DedupKey::FilenameCreated, global = true)]
+    dedup_key: DedupKey,
+
+    /// Keep every task found, even if it looks like a duplicate (same as --dedup-key none)
+    #[arg(long, global = true)]
+    no_dedup: bool,
+
+    /// Follow symlinked files and folders while scanning
+    #[arg(long, global = true)]
+    follow_links: bool,
+
+    /// Warn about statuses that are close to, but not exactly, a done or cancelled one
+    #[arg(long, global = true)]
+    fuzzy_status: bool,
+
+    /// Cache parsed tasks in this file and only re-parse notes that changed since the last run
+    #[arg(long, global = true)]
+    cache: Option<PathBuf>,
+
+    /// Extra status counted as done, on top of done/completed/x (repeatable or comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    done_status: Vec<String>,
+
+    /// Extra status counted as cancelled, on top of cancelled/canceled (repeatable or comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    cancelled_status: Vec<String>,
+
+    /// Only include tasks with one of these priorities (comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    priority: Vec<String>,
+
+    /// Only include tasks at this priority or above; tasks without a priority are left out
+    #[arg(long, value_name = "LEVEL", global = true)]
+    min_priority: Option<String>,
+
+    /// Only include tasks carrying one of these tags (repeatable or comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    tag: Vec<String>,
+
+    /// Treat --tag values as prefixes, so `work` also matches `work/clientA`
+    #[arg(long, global = true)]
+    tag_prefix: bool,
+
+    /// Only include tasks with one of these contexts, with or without `@` (comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    context: Vec<String>,
+
+    /// Only include tasks with this `taskSourceType`, e.g. `inline` (case-insensitive)
+    #[arg(long, global = true)]
+    source_type: Option<String>,
+
+    /// Match --tag, --project, --priority, --context and --source-type exactly
+    /// instead of ignoring case
+    #[arg(long, global = true)]
+    case_sensitive: bool,
+
+    /// Only include tasks belonging to this project
+    #[arg(long, global = true)]
+    project: Option<String>,
+
+    /// Only include tasks that are done
+    #[arg(long, global = true, conflicts_with = "not_done")]
+    done: bool,
+
+    /// Only include tasks that are not done
+    #[arg(long, global = true)]
+    not_done: bool,
+
+    /// Only include tasks due on or before this date
+    #[arg(long, value_name = "DATE", global = true)]
+    due_before: Option<NaiveDate>,
+
+    /// Only include tasks whose note file changed since a date (`2024-05-01`)
+    /// or within a recent span (`30m`, `12h`, `3d`, `2w`)
+    #[arg(long, global = true)]
+    modified_since: Option<Since>,
+
+    /// Only include tasks whose `dateCreated` is today
+    #[arg(long, global = true)]
+    created_today: bool,
+
+    /// Only include tasks due on or after this date
+    #[arg(long, value_name = "DATE", global = true)]
+    due_after: Option<NaiveDate>,
+
+    /// Sort list output by this key
+    #[arg(long, value_enum, global = true)]
+    sort: Option<SortKey>,
+
+    /// Break ties in --sort by these keys, in order (repeatable or comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        global = true,
+        requires = "sort"
+    )]
+    then: Vec<SortKey>,
+
+    /// Reverse the sort order (tasks without a value still sort last)
+    #[arg(long, global = true, requires = "sort")]
+    reverse: bool,
+
+    /// Group list output into sections (JSON: an object of arrays; CSV: a `group` column) by this field
+    #[arg(long, value_enum, global = true)]
+    group_by: Option<GroupKey>,
+
+    /// Print only the number of matching tasks instead of listing them
+    #[arg(long, global = true)]
+    count_only: bool,
+
+    /// Choose one of the listed tasks interactively (with fzf when installed) and print
+    /// only that one. Without a terminal the whole list is printed as usual
+    #[arg(long, global = true)]
+    pick: bool,
+
+    /// For commands that write notes (add, complete): print each note's path and the
+    /// frontmatter that would be written, without touching disk
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Wrap JSON list output in an object with `generated_at`, `query`, `count` and `tasks`
+    #[arg(long, global = true)]
+    envelope: bool,
+
+    /// Exit with status 2 when no tasks match (the empty result is still printed)
+    #[arg(long, global = true)]
+    fail_empty: bool,
+
+    /// Follow list output with the summed `timeEstimate` of the listed tasks
+    /// (on stderr for json, jsonl and csv, so stdout stays machine-readable)
+    #[arg(long, global = true)]
+    total_effort: bool,
+
+    /// Show at most N tasks, applied after filtering and sorting
+    #[arg(long, global = true)]
+    limit: Option<usize>,
 
     #[command(subcommand)]
     command: Commands,
@@ -23,13 +309,125 @@ enum Commands {
     /// Show all tasks
     All,
     /// Show today's tasks (due today)
-    Today,
-    /// Show overdue tasks
-    Overdue,
-    /// Show pending (not done) tasks
-    Pending,
+    Today {
+        /// Leave out tasks whose `start` (or `scheduled`) date is still in the future
+        #[arg(long)]
+        hide_future: bool,
+    },
+    /// Show tasks scheduled for today, regardless of their due date
+    Scheduled,
+    /// Show overdue tasks, longest overdue first unless --sort is given
+    Overdue {
+        /// Also show done tasks that were completed after their due date
+        #[arg(long)]
+        include_done: bool,
+    },
+    /// Show pending tasks (neither done nor cancelled)
+    Pending {
+        /// Only tasks created more than N days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<i64>,
+        /// With --older-than, also keep tasks that have no `dateCreated`
+        #[arg(long, requires = "older_than")]
+        include_undated: bool,
+        /// Leave out tasks whose `dependsOn` tasks aren't done or cancelled yet
+        #[arg(long)]
+        actionable: bool,
+        /// Leave out tasks whose `start` (or `scheduled`) date is still in the future
+        #[arg(long)]
+        hide_future: bool,
+    },
+    /// Show tasks being worked on: open with a status of `in-progress`, `doing` or `/`
+    Doing,
     /// Show tasks completed today
     CompletedToday,
+    /// Show tasks created today (by `dateCreated`), done or not
+    CreatedToday,
+    /// Show tasks completed within a date range (inclusive)
+    Completed {
+        /// Earliest completion date to include (no lower bound when omitted)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        /// Latest completion date to include [default: today]
+        #[arg(long)]
+        until: Option<NaiveDate>,
+    },
+    /// Show the single most urgent pending task (overdue, then due date, then priority)
+    Next,
+    /// Show the few things that matter: pending high-priority tasks that are
+    /// overdue or due today, most urgent first unless --sort is given
+    Focus {
+        /// Lowest priority to include
+        #[arg(long, value_name = "LEVEL", default_value = "high")]
+        level: String,
+        /// Also include tasks due up to N days from today
+        #[arg(long, default_value_t = 0)]
+        days: u32,
+    },
+    /// Show pending tasks due between tomorrow and N days from today
+    Upcoming {
+        #[arg(default_value_t = 7)]
+        days: u32,
+        /// List each occurrence of recurring tasks that falls in the window
+        #[arg(long)]
+        expand_recurring: bool,
+    },
+    /// Show pending tasks due in the current week, overdue days included
+    ThisWeek {
+        /// First day of the week, e.g. `sunday` [default: monday, as in ISO weeks]
+        #[arg(long, default_value = "monday", value_parser = parse_weekday)]
+        week_start: Weekday,
+    },
+    /// Show pending tasks with neither a due nor a scheduled date: the undated backlog
+    Someday,
+    /// Show all tasks grouped by project
+    ByProject,
+    /// Show the current month as a grid with the number of open tasks due each day
+    Calendar {
+        /// First day of the week, e.g. `sunday` [default: monday, as in ISO weeks]
+        #[arg(long, default_value = "monday", value_parser = parse_weekday)]
+        week_start: Weekday,
+    },
+    /// Export tasks with a due date as an iCalendar (.ics) feed
+    Ics {
+        /// Leave out tasks that are already done
+        #[arg(long)]
+        hide_done: bool,
+    },
+    /// Show tasks whose title or note body contains the query (case-insensitive)
+    Search {
+        query: String,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Create a new open task note in --path, named after its title
+    Add {
+        title: String,
+        /// Due date (YYYY-MM-DD)
+        #[arg(long)]
+        due: Option<NaiveDate>,
+        /// Priority of the new task, e.g. `high`
+        #[arg(long)]
+        priority: Option<String>,
+        /// Tags of the new task (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+    },
+    /// Mark a task done: set `status: done` and `completedDate` to today in its note
+    Complete {
+        /// Filename of the note, with or without `.md` or wikilink brackets
+        filename: String,
+    },
+    /// Check the vault path and report what a scan finds, to troubleshoot an empty list
+    Doctor,
+    /// Lint task frontmatter and exit non-zero if any errors are found
+    Validate,
+    /// Print a JSON Schema describing the task objects in JSON output, limited
+    /// to `--fields` when given
+    Schema,
+    /// Summarize the vault: totals, overdue, and breakdowns by priority and project
+    Stats,
     /// Show only count (for waybar)
     Count {
         #[arg(long)]
@@ -38,173 +436,819 @@ enum Commands {
         overdue: bool,
         #[arg(long)]
         completed_today: bool,
+        /// Count open tasks whose status is `in-progress`, `doing` or `/`
+        #[arg(long)]
+        in_progress: bool,
+        /// Print a waybar JSON object with text, tooltip and class
+        #[arg(long)]
+        waybar: bool,
+        /// With --waybar, the class used when something is due today
+        #[arg(
+            long,
+            value_name = "CLASS",
+            default_value = "warning",
+            requires = "waybar"
+        )]
+        warn_class: String,
+        /// With --waybar, the class used when something is overdue
+        #[arg(
+            long,
+            value_name = "CLASS",
+            default_value = "critical",
+            requires = "waybar"
+        )]
+        critical_class: String,
+        /// Exit with status 1 when the count is greater than N (the count is still printed)
+        #[arg(long, value_name = "N")]
+        fail_over: Option<usize>,
     },
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct Task {
-    #[serde(skip)]
-    filename: String,
-    status: String,
-    #[serde(default)]
-    priority: Option<String>,
-    #[serde(rename = "dateCreated")]
-    date_created: Option<String>,
-    #[serde(default)]
-    tags: Vec<String>,
-    #[serde(default)]
-    projects: Vec<String>,
-    #[serde(default)]
-    due: Option<NaiveDate>,
-    #[serde(rename = "completedDate", default)]
-    completed_date: Option<NaiveDate>,
-    #[serde(rename = "taskSourceType", default)]
-    task_source_type: Option<String>,
-}
-
-impl Task {
-    fn is_done(&self) -> bool {
-        let s = self.status.to_lowercase();
-        s == "done" || s == "completed" || s == "x"
-    }
-
-    fn is_due_today(&self) -> bool {
-        if let Some(due) = self.due {
-            due == Local::now().date_naive()
-        } else {
-            false
+impl Cli {
+    /// Fill in anything not given on the command line from the config file
+    fn merge_config(&mut self, config: Config) {
+        if self.path.is_empty() {
+            self.path.extend(config.path);
         }
+        self.format = self.format.or(config.format);
+        self.timezone = self.timezone.take().or(config.timezone);
+        self.exclude.splice(0..0, config.exclude);
+        self.done_status.splice(0..0, config.done_statuses);
+        self.cancelled_status
+            .splice(0..0, config.cancelled_statuses);
     }
 
-    fn is_overdue(&self) -> bool {
-        if let Some(due) = self.due {
-            !self.is_done() && due < Local::now().date_naive()
-        } else {
-            false
+    fn format(&self) -> OutputFormat {
+        self.format.unwrap_or(OutputFormat::Json)
+    }
+
+    /// Whether list output gets wrapped; only JSON arrays have an envelope
+    fn envelope(&self) -> bool {
+        self.envelope && matches!(self.format(), OutputFormat::Json)
+    }
+
+    /// The moment date filters are judged at: the start of the --as-of day, or
+    /// the current time. Dates written into notes always use the real one.
+    fn now(&self) -> NaiveDateTime {
+        match self.as_of {
+            Some(date) => date.and_time(NaiveTime::MIN),
+            None => clock::now(clock::timezone()),
         }
     }
 
-    fn is_completed_today(&self) -> bool {
-        if let Some(completed) = self.completed_date {
-            completed == Local::now().date_naive()
-        } else {
-            false
+    fn render_options(&self, now: NaiveDateTime) -> RenderOptions {
+        RenderOptions {
+            format: self.format(),
+            color: self.color.enabled(),
+            relative_dates: self.relative_dates,
+            compact: self.compact,
+            with_computed: self.with_computed,
+            fields: (!self.fields.is_empty()).then(|| self.fields.clone()),
+            vault_name: self.vault_name.clone(),
+            include_body: self.include_body,
+            now,
         }
     }
 }
 
-fn extract_frontmatter(content: &str) -> Option<String> {
-    let lines: Vec<&str> = content.lines().collect();
-    
-    if lines.is_empty() || lines[0] != "---" {
-        return None;
+/// What a run works from besides the command line itself, resolved once the
+/// flags are parsed
+struct Session {
+    /// The subcommand name, e.g. `overdue`, for --envelope
+    query: String,
+    /// Notes skipped by the last scan, for --envelope
+    warnings: Vec<ScanWarning>,
+    /// `count --waybar` classes, with the tiers from the config file
+    waybar: WaybarClasses,
+    /// The moment date filters are judged at; see [`Cli::now`]
+    now: NaiveDateTime,
+}
+
+/// A weekday name such as `monday` or `sun`
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.parse()
+        .map_err(|_| format!("expected a weekday such as `monday` or `sun`, got `{}`", s))
+}
+
+/// Apply --sort, --then, --reverse and --limit to a filtered task list
+fn arrange(tasks: &mut Vec<&Task>, cli: &Cli) {
+    if let Some(key) = cli.sort {
+        let keys: Vec<SortKey> = std::iter::once(key)
+            .chain(cli.then.iter().copied())
+            .collect();
+        sort_tasks(tasks, &keys, cli.reverse);
+    }
+    if let Some(limit) = cli.limit {
+        tasks.truncate(limit);
     }
+}
 
-    for (i, line) in lines.iter().enumerate().skip(1) {
-        if *line == "---" {
-            return Some(lines[1..i].join("\n"));
+/// Sort, limit, group and render (or just count) a filtered task list according to
+/// the CLI options
+fn print_tasks(mut tasks: Vec<&Task>, cli: &Cli, session: &Session) -> Result<()> {
+    arrange(&mut tasks, cli);
+    if cli.pick && !cli.count_only && pick::is_interactive() {
+        if let Some(i) = pick::pick(&tasks)? {
+            print_task(tasks[i], cli, session)?;
         }
+        exit_if_empty(tasks.is_empty(), cli);
+        return Ok(());
     }
-
-    None
+    let output = match cli.group_by {
+        _ if cli.count_only => tasks.len().to_string(),
+        _ if cli.envelope() => {
+            let listing = match cli.group_by {
+                Some(key) => Listing::Groups(&group_tasks(&tasks, key)),
+                None => Listing::List(&tasks),
+            };
+            render_envelope(
+                listing,
+                &session.query,
+                &session.warnings,
+                &cli.render_options(session.now),
+            )?
+        }
+        Some(key) => render_groups(&group_tasks(&tasks, key), &cli.render_options(session.now))?,
+        None => render_tasks(&tasks, &cli.render_options(session.now))?,
+    };
+    // An empty list prints nothing rather than a blank line, except for JSON's `[]`
+    if !output.is_empty() {
+        outln!("{}", output);
+    }
+    if cli.total_effort {
+        let minutes = total_effort(tasks.iter().copied());
+        let summary = format!(
+            "total effort: {} ({} min)",
+            format_minutes(minutes),
+            minutes
+        );
+        match cli.format() {
+            OutputFormat::Plain | OutputFormat::Table => outln!("{}", summary),
+            _ => eprintln!("{}", summary),
+        }
+    }
+    exit_if_empty(tasks.is_empty(), cli);
+    Ok(())
 }
 
-fn parse_task_file(path: &Path) -> Result<Task> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+/// Print one task on its own: a bare object rather than an array for JSON
+fn print_task(task: &Task, cli: &Cli, session: &Session) -> Result<()> {
+    match cli.format() {
+        OutputFormat::Json => {
+            let json = TaskJson::new(task, &cli.render_options(session.now))?;
+            outln!("{}", to_json(&json, cli.compact)?)
+        }
+        _ => outln!(
+            "{}",
+            render_tasks(&[task], &cli.render_options(session.now))?
+        ),
+    }
+    Ok(())
+}
 
-    let frontmatter = extract_frontmatter(&content)
-        .context("No frontmatter found")?;
+/// Honor --fail-empty once an empty result has been printed. Exiting would
+/// end --watch, so it only applies to one-off runs.
+fn exit_if_empty(empty: bool, cli: &Cli) {
+    if empty && cli.fail_empty && !cli.watch {
+        std::process::exit(2);
+    }
+}
 
-    let mut task: Task = serde_yaml::from_str(&frontmatter)
-        .with_context(|| format!("Failed to parse YAML in: {}", path.display()))?;
+fn apply_filters(tasks: &mut Vec<Task>, cli: &Cli, now: NaiveDateTime) {
+    let modified_since = cli.modified_since.map(|since| since.resolve(now));
+    let case = if cli.case_sensitive {
+        CaseMode::Sensitive
+    } else {
+        CaseMode::Insensitive
+    };
+    tasks.retain(|t| {
+        t.has_priority(&cli.priority, case)
+            && cli
+                .min_priority
+                .as_deref()
+                .is_none_or(|level| t.priority_at_least(level))
+            && t.matches_tags(&cli.tag, cli.tag_prefix, case)
+            && t.has_context(&cli.context, case)
+            && cli
+                .source_type
+                .as_deref()
+                .is_none_or(|s| t.has_source_type(s, case))
+            && cli.project.as_deref().is_none_or(|p| t.in_project(p, case))
+            && (!cli.done || t.is_done())
+            && (!cli.not_done || !t.is_done())
+            && ((cli.due_after.is_none() && cli.due_before.is_none())
+                || t.due_in_range(cli.due_after, cli.due_before))
+            && modified_since.is_none_or(|since| t.modified_since(since))
+            && (!cli.created_today || t.is_created_today(now.date()))
+    });
+}
 
-    task.filename = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+/// Index `dependsOn` references, warning about any that point at no scanned task
+fn resolve_dependencies(tasks: &[Task]) -> Dependencies {
+    let dependencies = Dependencies::new(tasks);
+    for task in tasks {
+        for dep in dependencies.dangling(task) {
+            eprintln!(
+                "warning: {}: depends on `{}`, which matches no task",
+                task.path.display(),
+                dep
+            );
+        }
+    }
+    dependencies
+}
 
-    Ok(task)
+/// Find the single note called `filename` and mark it done
+fn complete(paths: &[PathBuf], options: &ScanOptions, filename: &str, dry_run: bool) -> Result<()> {
+    let wanted = dependency_name(filename);
+    let tasks = collect_tasks_from(paths, options)?;
+    let matches: Vec<&Task> = tasks.iter().filter(|t| t.filename == wanted).collect();
+    let task = match matches.as_slice() {
+        [] => anyhow::bail!("No task named `{}` in the scanned vaults", wanted),
+        [task] => task,
+        _ => {
+            let paths: Vec<String> = matches
+                .iter()
+                .map(|t| t.path.display().to_string())
+                .collect();
+            anyhow::bail!("`{}` matches several notes: {}", wanted, paths.join(", "))
+        }
+    };
+    if task.is_done() {
+        eprintln!("{} is already done", task.path.display());
+        return Ok(());
+    }
+    let write = plan_completion(&task.path, clock::today(clock::timezone()))?;
+    if dry_run {
+        return print_dry_run(&write);
+    }
+    write.apply()?;
+    outln!("{}", task.path.display());
+    Ok(())
 }
 
-/// Helper to scan a directory for .md files and add them to the tasks vector
-fn scan_dir(path: &Path, tasks: &mut Vec<Task>) {
-    if !path.exists() || !path.is_dir() {
-        return;
+/// Show what a note write would do instead of doing it
+fn print_dry_run(write: &NoteWrite) -> Result<()> {
+    let action = match write.kind {
+        WriteKind::Create => "create",
+        WriteKind::Replace => "update",
+    };
+    outln!("would {} {}", action, write.path.display());
+    let frontmatter = write.frontmatter();
+    out!("{}", frontmatter);
+    if !frontmatter.ends_with('\n') {
+        outln!("");
     }
+    Ok(())
+}
 
-    for entry in WalkDir::new(path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()).map(|ext| ext.to_lowercase()) == Some("md".to_string()))
-    {
-        if let Ok(task) = parse_task_file(entry.path()) {
-            // Check if task already exists in list to avoid duplicates if Archive is a subfolder
-            if !tasks.iter().any(|t| t.filename == task.filename && t.date_created == task.date_created) {
-                tasks.push(task);
+/// Lint the vault, print the issues and exit non-zero if any are errors
+fn validate(paths: &[PathBuf], options: &ScanOptions, cli: &Cli, now: NaiveDateTime) -> Result<()> {
+    let issues = validate_vault(paths, options, now);
+    match cli.format() {
+        OutputFormat::Json => outln!("{}", to_json(&issues, cli.compact)?),
+        OutputFormat::Jsonl => {
+            if !issues.is_empty() {
+                outln!("{}", json_lines(&issues)?);
             }
         }
+        _ if issues.is_empty() => outln!("No issues found"),
+        _ => outln!("{}", render_issues_plain(&issues)),
+    }
+    if issues.iter().any(|i| i.severity == Severity::Error) {
+        std::process::exit(1);
     }
+    Ok(())
 }
 
-fn collect_tasks(vault_path: &Path) -> Result<Vec<Task>> {
-    let mut tasks = Vec::new();
+fn main() -> Result<()> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let config = match &cli.config {
+        Some(path) => load_config_from(path)?,
+        None => load_config()?,
+    };
+    let waybar = WaybarClasses {
+        tiers: config.waybar.tiers.clone(),
+        ..WaybarClasses::default()
+    };
+    if !config.priorities.is_empty() {
+        priority::set_levels(config.priorities.clone());
+    }
+    status::set_symbols(config.status_symbols.clone());
+    cli.merge_config(config);
+    if let Some(level) = &cli.min_priority {
+        if priority::rank(Some(level)) == 0 {
+            anyhow::bail!(
+                "Unknown priority `{}` for --min-priority; levels from lowest to highest are: {}",
+                level,
+                priority::levels().join(", ")
+            );
+        }
+    }
+    if let Commands::Focus { level, .. } = &cli.command {
+        if priority::rank(Some(level)) == 0 {
+            anyhow::bail!(
+                "Unknown priority `{}` for --level; levels from lowest to highest are: {}",
+                level,
+                priority::levels().join(", ")
+            );
+        }
+    }
+    let valid_fields = field_names();
+    if let Some(unknown) = cli.fields.iter().find(|f| !valid_fields.contains(f)) {
+        anyhow::bail!(
+            "Unknown field `{}` for --fields; valid fields are: {}",
+            unknown,
+            valid_fields.join(", ")
+        );
+    }
+    if let Some(root) = &cli.vault_root {
+        let folders = vault::locate(root)?;
+        if !folders.from_settings && !cli.quiet {
+            eprintln!(
+                "warning: no TaskNotes tasks folder set in {}; scanning the whole vault",
+                root.join(vault::PLUGIN_SETTINGS).display()
+            );
+        }
+        if !folders.from_settings {
+            cli.exclude
+                .extend([".obsidian".to_string(), ".trash".to_string()]);
+        }
+        cli.path = vec![folders.tasks];
+        cli.archive.extend(folders.archive);
+    }
+    if let Some(tz) = cli.timezone.clone() {
+        clock::set_timezone(tz);
+    }
+    let mut session = Session {
+        query: matches.subcommand_name().unwrap_or_default().to_string(),
+        warnings: Vec::new(),
+        waybar,
+        now: cli.now(),
+    };
+    if let Some(path) = &cli.output {
+        output::to_file(path)?;
+    }
+    // Describes the output rather than a vault, so no path is needed
+    if let Commands::Schema = cli.command {
+        outln!("{}", to_json(&task_schema_for(&cli.fields), cli.compact)?);
+        return Ok(());
+    }
+    let scan_options = ScanOptions {
+        strict: cli.strict,
+        threads: cli.threads,
+        exclude: cli.exclude.clone(),
+        archives: cli.archive.clone(),
+        no_sibling_archive: cli.no_archive,
+        follow_links: cli.follow_links,
+        statuses: Statuses::with_extra(&cli.done_status, &cli.cancelled_status),
+        fuzzy_status: cli.fuzzy_status,
+        dedup: if cli.no_dedup {
+            DedupKey::None
+        } else {
+            cli.dedup_key
+        },
+        cache: cli.cache.clone(),
+        quiet: cli.quiet,
+        verbose: cli.verbose,
+        extensions: cli.ext.clone(),
+        default_status: cli.default_status.clone(),
+    };
 
-    // 1. Scan the main TaskNotes directory (and its subfolders like Archive/)
-    scan_dir(vault_path, &mut tasks);
+    if cli.stdin {
+        match cli.command {
+            Commands::Validate | Commands::Doctor => {
+                anyhow::bail!("validate and doctor scan a vault folder and can't read from --stdin")
+            }
+            Commands::Add { .. } | Commands::Complete { .. } => {
+                anyhow::bail!("add and complete write into --path and can't use --stdin")
+            }
+            _ => {}
+        }
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .context("Failed to read stdin")?;
+        let mut task = parse_task_str_with(&content, "stdin", &scan_options)?;
+        task.path = PathBuf::from("-");
+        return run(vec![task], &cli, &session);
+    }
 
-    // 2. Explicitly check for an 'Archive' folder that might be a sibling 
-    // (In case your CLI path points to 'Tasks' but archive is at 'Archive')
-    if let Some(parent) = vault_path.parent() {
-        let archive_sibling = parent.join("Archive");
-        if archive_sibling.exists() && archive_sibling != vault_path {
-            scan_dir(&archive_sibling, &mut tasks);
+    if cli.path.is_empty() {
+        anyhow::bail!(
+            "No vault path given: pass --path or set `path` in {}",
+            CONFIG_FILE_NAME
+        );
+    }
+    let mut paths = Vec::new();
+    for pattern in &cli.path {
+        let matches = expand_path_glob(pattern);
+        if matches.is_empty() {
+            eprintln!("warning: --path {} matches no folder", pattern.display());
+        }
+        paths.extend(matches);
+    }
+    match cli.command {
+        Commands::Validate => return validate(&paths, &scan_options, &cli, session.now),
+        Commands::Add {
+            ref title,
+            due,
+            ref priority,
+            ref tag,
+        } => {
+            let [path] = paths.as_slice() else {
+                anyhow::bail!("add writes into one folder; pass a single --path");
+            };
+            let task = NewTask {
+                title: title.clone(),
+                due,
+                priority: priority.clone(),
+                tags: tag.clone(),
+            };
+            let write = task.plan_in(path)?;
+            if cli.dry_run {
+                return print_dry_run(&write);
+            }
+            write.apply()?;
+            outln!("{}", write.path.display());
+            return Ok(());
+        }
+        Commands::Complete { ref filename } => {
+            return complete(&paths, &scan_options, filename, cli.dry_run)
+        }
+        Commands::Doctor => {
+            let diagnoses: Vec<Diagnosis> = paths
+                .iter()
+                .map(|path| Diagnosis::run(path, &scan_options))
+                .collect();
+            // A single vault keeps printing one object rather than a list of one
+            match (cli.format(), diagnoses.as_slice()) {
+                (OutputFormat::Json, [diagnosis]) => {
+                    outln!("{}", to_json(diagnosis, cli.compact)?)
+                }
+                (OutputFormat::Json, _) => outln!("{}", to_json(&diagnoses, cli.compact)?),
+                (OutputFormat::Jsonl, _) => outln!("{}", json_lines(&diagnoses)?),
+                _ => {
+                    let reports: Vec<String> =
+                        diagnoses.iter().map(Diagnosis::render_plain).collect();
+                    outln!("{}", reports.join("\n\n"));
+                }
+            }
+            return Ok(());
         }
+        _ => {}
     }
 
-    Ok(tasks)
+    if !cli.watch {
+        let (tasks, warnings) = collect_tasks_reporting(&paths, &scan_options)?;
+        session.warnings = warnings;
+        return run(tasks, &cli, &session);
+    }
+    let mut snapshot = watch::snapshot(&paths, &scan_options);
+    loop {
+        // Keep watching through a bad edit (e.g. a --strict failure mid-save)
+        match collect_tasks_reporting(&paths, &scan_options) {
+            Ok((tasks, warnings)) => {
+                if let Some(path) = &cli.output {
+                    output::to_file(path)?;
+                }
+                session.warnings = warnings;
+                session.now = cli.now();
+                run(tasks, &cli, &session)?
+            }
+            Err(e) => eprintln!("error: {:#}", e),
+        }
+        snapshot = watch::wait_for_change(&paths, &scan_options, &snapshot);
+    }
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    let tasks = collect_tasks(&cli.path)?;
+/// Filter the scanned tasks and print the result of the subcommand
+fn run(mut tasks: Vec<Task>, cli: &Cli, session: &Session) -> Result<()> {
+    // Dependencies resolve against the whole scan, before filters narrow it
+    let dependencies = match cli.command {
+        Commands::Pending {
+            actionable: true, ..
+        } => Some(resolve_dependencies(&tasks)),
+        _ => None,
+    };
+    let now = session.now;
+    let today = now.date();
+    apply_filters(&mut tasks, cli, now);
+    if cli.normalize_status {
+        tasks.iter_mut().for_each(Task::normalize_status);
+    }
 
     match cli.command {
         Commands::All => {
-            println!("{}", serde_json::to_string_pretty(&tasks)?);
+            print_tasks(tasks.iter().collect(), cli, session)?;
+        }
+        Commands::Today { hide_future } => {
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_due_today(today))
+                .filter(|t| !hide_future || t.has_started(today))
+                .collect();
+            print_tasks(filtered, cli, session)?;
         }
-        Commands::Today => {
-            let filtered: Vec<_> = tasks.iter().filter(|t| t.is_due_today()).collect();
-            println!("{}", serde_json::to_string_pretty(&filtered)?);
+        Commands::Scheduled => {
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_scheduled_today(today))
+                .collect();
+            print_tasks(filtered, cli, session)?;
         }
-        Commands::Overdue => {
-            let filtered: Vec<_> = tasks.iter().filter(|t| t.is_overdue()).collect();
-            println!("{}", serde_json::to_string_pretty(&filtered)?);
+        Commands::Overdue { include_done } => {
+            let mut filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_overdue(now) || (include_done && t.was_completed_late()))
+                .collect();
+            // Longest overdue first unless another order was asked for
+            if cli.sort.is_none() {
+                sort_tasks(&mut filtered, &[SortKey::Due], false);
+            }
+            print_tasks(filtered, cli, session)?;
         }
-        Commands::Pending => {
-            let filtered: Vec<_> = tasks.iter().filter(|t| !t.is_done()).collect();
-            println!("{}", serde_json::to_string_pretty(&filtered)?);
+        Commands::Pending {
+            older_than,
+            include_undated,
+            actionable,
+            hide_future,
+        } => {
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_open())
+                .filter(|t| !hide_future || t.has_started(today))
+                .filter(|t| !actionable || dependencies.as_ref().is_none_or(|d| !d.is_blocked(t)))
+                .filter(|t| match (older_than, t.age_days(today)) {
+                    (None, _) => true,
+                    (Some(days), Some(age)) => age > days,
+                    (Some(_), None) => include_undated,
+                })
+                .collect();
+            print_tasks(filtered, cli, session)?;
         }
         Commands::CompletedToday => {
-            let filtered: Vec<_> = tasks.iter().filter(|t| t.is_completed_today()).collect();
-            println!("{}", serde_json::to_string_pretty(&filtered)?);
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_completed_today(today))
+                .collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Doing => {
+            let filtered: Vec<_> = tasks.iter().filter(|t| t.is_in_progress()).collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::CreatedToday => {
+            let filtered: Vec<_> = tasks.iter().filter(|t| t.is_created_today(today)).collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Completed { since, until } => {
+            let until = until.unwrap_or(today);
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.completed_between(since, Some(until)))
+                .collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Next => {
+            let next = most_urgent(&tasks, now);
+            match (next, cli.format()) {
+                (task, _) if cli.count_only => outln!("{}", usize::from(task.is_some())),
+                (None, OutputFormat::Json) => outln!("null"),
+                (None, _) => {}
+                (Some(task), _) => print_task(task, cli, session)?,
+            }
+            exit_if_empty(next.is_none(), cli);
+        }
+        Commands::Focus { ref level, days } => {
+            let until = today + Days::new(days as u64);
+            let mut filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| {
+                    t.is_open() && t.due_in_range(None, Some(until)) && t.priority_at_least(level)
+                })
+                .collect();
+            if cli.sort.is_none() {
+                filtered.sort_by(|a, b| cmp_urgency(a, b, now));
+            }
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Someday => {
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_open() && t.is_undated())
+                .collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::ThisWeek { week_start } => {
+            let filtered: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.is_due_this_week(week_start, today))
+                .collect();
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Upcoming {
+            days,
+            expand_recurring,
+        } => {
+            if !expand_recurring {
+                let filtered: Vec<_> = tasks
+                    .iter()
+                    .filter(|t| t.is_due_within(days, today))
+                    .collect();
+                print_tasks(filtered, cli, session)?;
+                return Ok(());
+            }
+
+            let (from, to) = (today + Days::new(1), today + Days::new(days as u64));
+            let mut expanded = Vec::new();
+            for task in tasks.iter().filter(|t| t.is_open()) {
+                match task.expand_recurring(from, to) {
+                    Some(Ok(instances)) => expanded.extend(instances),
+                    Some(Err(e)) => {
+                        eprintln!(
+                            "warning: ignoring recurrence of {}: {:#}",
+                            task.display_name(),
+                            e
+                        );
+                        if task.is_due_within(days, today) {
+                            expanded.push(task.clone());
+                        }
+                    }
+                    None if task.is_due_within(days, today) => expanded.push(task.clone()),
+                    None => {}
+                }
+            }
+            print_tasks(expanded.iter().collect(), cli, session)?;
+        }
+        Commands::ByProject => {
+            if cli.count_only {
+                outln!("{}", tasks.len());
+            } else {
+                let mut groups = group_by_project(&tasks);
+                for group in groups.values_mut() {
+                    arrange(group, cli);
+                }
+                let options = cli.render_options(session.now);
+                let output = if cli.envelope() {
+                    render_envelope(
+                        Listing::Groups(&groups),
+                        &session.query,
+                        &session.warnings,
+                        &options,
+                    )?
+                } else {
+                    render_groups(&groups, &options)?
+                };
+                outln!("{}", output);
+            }
+            exit_if_empty(tasks.is_empty(), cli);
+        }
+        Commands::Calendar { week_start } => {
+            let tasks: Vec<_> = tasks.iter().collect();
+            let color = cli.color.enabled();
+            outln!("{}", render_month(&tasks, now, week_start, color));
+        }
+        Commands::Ics { hide_done } => {
+            let events: Vec<_> = tasks
+                .iter()
+                .filter(|t| !(hide_done && t.is_done()))
+                .collect();
+            out!("{}", render_ics(&events));
+            exit_if_empty(events.is_empty(), cli);
+        }
+        Commands::Search { ref query, regex } => {
+            let filtered: Vec<_> = if regex {
+                let pattern = Regex::new(query, true)?;
+                tasks.iter().filter(|t| t.matches_regex(&pattern)).collect()
+            } else {
+                tasks.iter().filter(|t| t.contains_text(query)).collect()
+            };
+            print_tasks(filtered, cli, session)?;
+        }
+        Commands::Validate
+        | Commands::Doctor
+        | Commands::Schema
+        | Commands::Add { .. }
+        | Commands::Complete { .. } => {
+            unreachable!("handled before collecting tasks")
         }
-        Commands::Count { today, overdue, completed_today } => {
-            let count = if today {
-                tasks.iter().filter(|t| t.is_due_today()).count()
+        Commands::Stats => {
+            let stats = Stats::compute(&tasks, now);
+            match cli.format() {
+                OutputFormat::Json => outln!("{}", to_json(&stats, cli.compact)?),
+                OutputFormat::Jsonl => outln!("{}", serde_json::to_string(&stats)?),
+                _ => outln!("{}", stats.render_plain()),
+            }
+        }
+        Commands::Count {
+            today: due_today,
+            overdue,
+            completed_today,
+            in_progress,
+            waybar,
+            ref warn_class,
+            ref critical_class,
+            fail_over,
+        } => {
+            let count = if due_today {
+                tasks.iter().filter(|t| t.is_due_today(today)).count()
             } else if overdue {
-                tasks.iter().filter(|t| t.is_overdue()).count()
+                tasks.iter().filter(|t| t.is_overdue(now)).count()
             } else if completed_today {
-                tasks.iter().filter(|t| t.is_completed_today()).count()
+                tasks.iter().filter(|t| t.is_completed_today(today)).count()
+            } else if in_progress {
+                tasks.iter().filter(|t| t.is_in_progress()).count()
             } else {
-                tasks.iter().filter(|t| !t.is_done()).count()
+                tasks.iter().filter(|t| t.is_open()).count()
             };
-            println!("{}", count);
+            if waybar {
+                let classes = WaybarClasses {
+                    warning: warn_class.clone(),
+                    critical: critical_class.clone(),
+                    ..session.waybar.clone()
+                };
+                let output = WaybarOutput::new(&tasks, count, &classes, now);
+                outln!("{}", serde_json::to_string(&output)?);
+            } else {
+                outln!("{}", count);
+            }
+            // Exiting would end --watch, so thresholds only apply to one-off runs
+            if fail_over.is_some_and(|n| count > n) && !cli.watch {
+                std::process::exit(1);
+            }
+            exit_if_empty(count == 0, cli);
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(["obsidian-tasks"].iter().chain(args)).unwrap()
+    }
+
+    fn config() -> Config {
+        obsidian_tasks::toml::from_str_as(
+            "path = \"/config/vault\"\n\
+             format = \"table\"\n\
+             timezone = \"Europe/Berlin\"\n\
+             exclude = [\"Templates\"]\n\
+             done_statuses = [\"shipped\"]\n\
+             cancelled_statuses = [\"dropped\"]\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn config_fills_in_what_the_command_line_leaves_out() {
+        let mut cli = cli(&["all"]);
+        cli.merge_config(config());
+        assert_eq!(cli.path, [PathBuf::from("/config/vault")]);
+        assert!(matches!(cli.format(), OutputFormat::Table));
+        assert_eq!(cli.timezone.as_ref().map(Tz::name), Some("Europe/Berlin"));
+        assert_eq!(cli.exclude, ["Templates"]);
+        assert_eq!(cli.done_status, ["shipped"]);
+        assert_eq!(cli.cancelled_status, ["dropped"]);
+    }
+
+    #[test]
+    fn flags_win_over_the_config_and_lists_add_up() {
+        let mut cli = cli(&[
+            "--path",
+            "/cli/vault",
+            "all",
+            "-f",
+            "csv",
+            "--timezone",
+            "UTC",
+            "--exclude",
+            "Drafts",
+            "--done-status",
+            "merged",
+            "--cancelled-status",
+            "wontfix",
+        ]);
+        cli.merge_config(config());
+        assert_eq!(cli.path, [PathBuf::from("/cli/vault")]);
+        assert!(matches!(cli.format(), OutputFormat::Csv));
+        assert_eq!(cli.timezone.as_ref().map(Tz::name), Some("UTC"));
+        assert_eq!(cli.exclude, ["Templates", "Drafts"]);
+        assert_eq!(cli.done_status, ["shipped", "merged"]);
+        assert_eq!(cli.cancelled_status, ["dropped", "wontfix"]);
+    }
+
+    #[test]
+    fn an_empty_config_changes_nothing() {
+        let mut cli = cli(&["--path", "/cli/vault", "all"]);
+        cli.merge_config(Config::default());
+        assert_eq!(cli.path, [PathBuf::from("/cli/vault")]);
+        assert!(matches!(cli.format(), OutputFormat::Json));
+        assert!(cli.timezone.is_none() && cli.exclude.is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+//! Writing task notes back to the vault.
+
+use crate::{clock, parse_task_str, FrontmatterFormat};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use serde_yaml::{Mapping, Value};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// The fields of a task captured from the command line
+#[derive(Debug, Clone, Default)]
+pub struct NewTask {
+    pub title: String,
+    pub due: Option<NaiveDate>,
+    pub priority: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Characters Obsidian won't accept in a note name
+const FORBIDDEN_IN_NAME: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// A note filename for `title`: forbidden characters dropped, whitespace
+/// collapsed, and no leading dot so the note isn't hidden
+pub fn note_filename(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .filter(|c| !FORBIDDEN_IN_NAME.contains(c) && !c.is_control())
+        .collect();
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    cleaned.trim_start_matches('.').trim().to_string()
+}
+
+impl NewTask {
+    /// The note content: YAML frontmatter with `status: open` and `dateCreated`
+    /// set to now, and an empty body
+    pub fn render(&self) -> Result<String> {
+        let mut map = Mapping::new();
+        let mut set = |key: &str, value: Value| {
+            map.insert(Value::String(key.to_string()), value);
+        };
+        set("title", Value::String(self.title.trim().to_string()));
+        set("status", Value::String("open".to_string()));
+        if let Some(priority) = &self.priority {
+            set("priority", Value::String(priority.trim().to_string()));
+        }
+        let created = clock::now_with_offset(clock::timezone());
+        set(
+            "dateCreated",
+            Value::String(created.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()),
+        );
+        if !self.tags.is_empty() {
+            let tags = self.tags.iter().map(|t| Value::String(t.clone())).collect();
+            set("tags", Value::Sequence(tags));
+        }
+        if let Some(due) = self.due {
+            set("due", Value::String(due.format("%Y-%m-%d").to_string()));
+        }
+        let yaml = serde_yaml::to_string(&map).context("Failed to write frontmatter")?;
+        Ok(format!("---\n{}---\n", yaml))
+    }
+
+    /// The new note for the task in `dir`, named after its title, without
+    /// writing it yet
+    pub fn plan_in(&self, dir: &Path) -> Result<NoteWrite> {
+        let name = note_filename(&self.title);
+        if name.is_empty() {
+            bail!(
+                "Task title {:?} leaves nothing to name the note after",
+                self.title
+            );
+        }
+        if !dir.is_dir() {
+            bail!("Task folder does not exist: {}", dir.display());
+        }
+        let path = dir.join(format!("{}.md", name));
+        if path.exists() {
+            bail!("A note already exists at {}", path.display());
+        }
+        Ok(NoteWrite {
+            path,
+            content: self.render()?,
+            kind: WriteKind::Create,
+        })
+    }
+
+    /// Write the task as a new note in `dir`, named after its title. An
+    /// existing note is never overwritten.
+    pub fn create_in(&self, dir: &Path) -> Result<PathBuf> {
+        let write = self.plan_in(dir)?;
+        write.apply()?;
+        Ok(write.path)
+    }
+}
+
+/// Whether a [`NoteWrite`] makes a new note or replaces an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteKind {
+    Create,
+    Replace,
+}
+
+/// A change to one note, worked out in full before anything touches disk so
+/// it can be previewed with `--dry-run`
+#[derive(Debug, Clone)]
+pub struct NoteWrite {
+    pub path: PathBuf,
+    /// The whole note as it will be written
+    pub content: String,
+    pub kind: WriteKind,
+}
+
+impl NoteWrite {
+    /// Write the note. A new note is never allowed to overwrite one that
+    /// appeared in the meantime, and a replacement goes through a temporary
+    /// file so a failure can't truncate the original.
+    pub fn apply(&self) -> Result<()> {
+        let path = &self.path;
+        match self.kind {
+            WriteKind::Create => {
+                let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                        bail!("A note already exists at {}", path.display())
+                    }
+                    Err(e) => {
+                        return Err(e)
+                            .with_context(|| format!("Failed to create {}", path.display()))
+                    }
+                };
+                file.write_all(self.content.as_bytes())
+                    .with_context(|| format!("Failed to write {}", path.display()))
+            }
+            WriteKind::Replace => {
+                let tmp = path.with_extension("md.tmp");
+                fs::write(&tmp, &self.content)
+                    .with_context(|| format!("Failed to write {}", tmp.display()))?;
+                fs::rename(&tmp, path)
+                    .with_context(|| format!("Failed to replace {}", path.display()))
+            }
+        }
+    }
+
+    /// The frontmatter block as it will be written, delimiters included
+    pub fn frontmatter(&self) -> &str {
+        let mut end = 0;
+        let mut delimiter = None;
+        for line in self.content.split_inclusive('\n') {
+            end += line.len();
+            let trimmed = line.trim_start_matches('\u{feff}').trim_end();
+            match delimiter {
+                None if trimmed.is_empty() => {}
+                None => delimiter = Some(trimmed),
+                Some(open) if trimmed == open => break,
+                Some(_) => {}
+            }
+        }
+        &self.content[..end]
+    }
+}
+
+/// Set top-level frontmatter keys in a note, leaving every other line as it
+/// was. An existing `key:` (or TOML `key =`) line, and any indented lines
+/// continuing its value, is replaced in place; missing keys are added at the
+/// end of the block. Values are quoted in TOML blocks but written as-is in
+/// YAML ones, so they must already be valid YAML scalars.
+pub fn set_frontmatter_fields(content: &str, fields: &[(&str, &str)]) -> Result<String> {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+    let trimmed = |line: &str| line.trim_start_matches('\u{feff}').trim_end().to_string();
+    let start = lines
+        .iter()
+        .position(|line| !trimmed(line).is_empty())
+        .context("No frontmatter found")?;
+    let format = [FrontmatterFormat::Yaml, FrontmatterFormat::Toml]
+        .into_iter()
+        .find(|format| trimmed(&lines[start]) == format.delimiter())
+        .context("No frontmatter found")?;
+    let mut end = (start + 1..lines.len())
+        .find(|&i| trimmed(&lines[i]) == format.delimiter())
+        .with_context(|| format!("Frontmatter is not closed with `{}`", format.delimiter()))?;
+    let eol = if lines[start].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    for (key, value) in fields {
+        let line = match format {
+            FrontmatterFormat::Yaml => format!("{}: {}{}", key, value, eol),
+            FrontmatterFormat::Toml => {
+                format!("{} = {}{}", key, serde_json::to_string(value)?, eol)
+            }
+        };
+        // TOML keys after a `[table]` header belong to that table, so only
+        // the lines before the first header are top-level
+        let top_level_end = match format {
+            FrontmatterFormat::Toml => (start + 1..end)
+                .find(|&i| lines[i].starts_with('['))
+                .unwrap_or(end),
+            FrontmatterFormat::Yaml => end,
+        };
+        match (start + 1..top_level_end).find(|&i| sets_key(&lines[i], key, format)) {
+            Some(i) => {
+                let continued = lines[i + 1..end]
+                    .iter()
+                    .take_while(|l| l.starts_with([' ', '\t']))
+                    .count();
+                lines.splice(i..i + 1 + continued, [line]);
+                end -= continued;
+            }
+            None => {
+                lines.insert(top_level_end, line);
+                end += 1;
+            }
+        }
+    }
+    Ok(lines.concat())
+}
+
+/// Whether a frontmatter line assigns top-level `key`
+fn sets_key(line: &str, key: &str, format: FrontmatterFormat) -> bool {
+    line.strip_prefix(key).is_some_and(|rest| match format {
+        FrontmatterFormat::Yaml => rest.starts_with(':'),
+        FrontmatterFormat::Toml => rest.trim_start().starts_with('='),
+    })
+}
+
+/// The change marking the note at `path` done on `today`: only its `status`
+/// and `completedDate` lines are rewritten, and the result is re-parsed so a
+/// bad edit is refused before anything is written.
+pub fn plan_completion(path: &Path, today: NaiveDate) -> Result<NoteWrite> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let completed = today.format("%Y-%m-%d").to_string();
+    let updated = set_frontmatter_fields(
+        &content,
+        &[("status", "done"), ("completedDate", &completed)],
+    )
+    .with_context(|| format!("Failed to update {}", path.display()))?;
+    parse_task_str(&updated, "").with_context(|| {
+        format!(
+            "Refusing to write unparseable frontmatter to {}",
+            path.display()
+        )
+    })?;
+    Ok(NoteWrite {
+        path: path.to_path_buf(),
+        content: updated,
+        kind: WriteKind::Replace,
+    })
+}
+
+/// Mark the note at `path` done on `today`; see [`plan_completion`]
+pub fn complete_note(path: &Path, today: NaiveDate) -> Result<()> {
+    plan_completion(path, today)?.apply()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DONE: [(&str, &str); 2] = [("status", "done"), ("completedDate", "2026-01-02")];
+
+    #[test]
+    fn filenames_drop_what_obsidian_refuses() {
+        assert_eq!(note_filename("Call the bank"), "Call the bank");
+        assert_eq!(note_filename("a/b: c?\t*d"), "ab cd");
+        assert_eq!(note_filename("  many\t  spaces\n"), "many spaces");
+        assert_eq!(note_filename("..hidden"), "hidden");
+        assert_eq!(note_filename("\"?\""), "");
+    }
+
+    #[test]
+    fn new_notes_are_open_with_the_given_fields() {
+        let task = NewTask {
+            title: " Call the bank ".to_string(),
+            due: NaiveDate::from_ymd_opt(2026, 6, 1),
+            priority: Some("high".to_string()),
+            tags: vec!["finance".to_string(), "home".to_string()],
+        };
+        let note = task.render().unwrap();
+        let parsed = parse_task_str(&note, "Call the bank").unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Call the bank"));
+        assert_eq!(parsed.status, "open");
+        assert_eq!(parsed.priority.as_deref(), Some("high"));
+        assert_eq!(parsed.tags, ["finance", "home"]);
+        assert_eq!(parsed.due.unwrap().to_string(), "2026-06-01");
+        assert!(parsed.date_created.is_some());
+        assert!(note.starts_with("---\ntitle: Call the bank\nstatus: open\npriority: high\n"));
+
+        let bare = NewTask {
+            title: "x".to_string(),
+            ..NewTask::default()
+        };
+        let note = bare.render().unwrap();
+        assert!(!note.contains("priority") && !note.contains("tags") && !note.contains("due"));
+    }
+
+    #[test]
+    fn an_existing_note_is_never_overwritten() {
+        let dir = crate::testutil::TempDir::new();
+        let existing = dir.write("Call the bank.md", "keep me");
+        let task = NewTask {
+            title: "Call the bank".to_string(),
+            ..NewTask::default()
+        };
+        let err = task.create_in(dir.path()).unwrap_err().to_string();
+        assert!(err.starts_with("A note already exists"), "{}", err);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "keep me");
+
+        let write = NewTask {
+            title: "Other".to_string(),
+            ..NewTask::default()
+        }
+        .plan_in(dir.path())
+        .unwrap();
+        dir.write("Other.md", "appeared meanwhile");
+        assert!(write.apply().is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("Other.md")).unwrap(),
+            "appeared meanwhile"
+        );
+        assert!(NewTask::default().plan_in(dir.path()).is_err());
+    }
+
+    #[test]
+    fn continuation_lines_are_replaced_with_the_key() {
+        let note = "---\nstatus:\n  open\ntags:\n  - a\n---\nbody\n";
+        assert_eq!(
+            set_frontmatter_fields(note, &DONE).unwrap(),
+            "---\nstatus: done\ntags:\n  - a\ncompletedDate: 2026-01-02\n---\nbody\n"
+        );
+    }
+
+    #[test]
+    fn toml_keys_are_quoted_and_kept_out_of_tables() {
+        let note = "+++\nstatus = \"open\"\n[extra]\nstatus = \"kept\"\n+++\n";
+        assert_eq!(
+            set_frontmatter_fields(note, &DONE).unwrap(),
+            "+++\nstatus = \"done\"\ncompletedDate = \"2026-01-02\"\n[extra]\nstatus = \"kept\"\n+++\n"
+        );
+    }
+
+    #[test]
+    fn crlf_and_a_byte_order_mark_survive() {
+        let note = "\u{feff}---\r\nstatus: open\r\n---\r\nbody\r\n";
+        assert_eq!(
+            set_frontmatter_fields(note, &DONE).unwrap(),
+            "\u{feff}---\r\nstatus: done\r\ncompletedDate: 2026-01-02\r\n---\r\nbody\r\n"
+        );
+    }
+
+    #[test]
+    fn missing_keys_are_appended_to_the_block() {
+        let note = "---\ntitle: x\n---\n";
+        assert_eq!(
+            set_frontmatter_fields(note, &DONE).unwrap(),
+            "---\ntitle: x\nstatus: done\ncompletedDate: 2026-01-02\n---\n"
+        );
+        assert!(set_frontmatter_fields("no frontmatter\n", &DONE).is_err());
+        assert!(set_frontmatter_fields("---\nstatus: open\n", &DONE).is_err());
+    }
+
+    #[test]
+    fn completion_keeps_the_body_byte_for_byte() {
+        let dir = crate::testutil::TempDir::new();
+        let body = "\n# Notes\n---\nstatus: open\n  indented\ttab \r\n\u{e9}\n";
+        let path = dir.write("task.md", &format!("---\nstatus: open\n---\n{}", body));
+        let today = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let write = plan_completion(&path, today).unwrap();
+        assert_eq!(write.kind, WriteKind::Replace);
+        assert_eq!(
+            write.content,
+            format!(
+                "---\nstatus: done\ncompletedDate: 2026-01-02\n---\n{}",
+                body
+            )
+        );
+        assert_eq!(
+            write.frontmatter(),
+            "---\nstatus: done\ncompletedDate: 2026-01-02\n---\n"
+        );
+        write.apply().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), write.content);
+    }
+
+    #[test]
+    fn completion_refuses_an_edit_that_would_not_parse() {
+        let dir = crate::testutil::TempDir::new();
+        let path = dir.write("task.md", "---\nstatus: open\ntags: [a\n---\n");
+        let today = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert!(plan_completion(&path, today).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+//! Where command output goes: stdout, or the file given with `--output`.
+//!
+//! Diagnostics and warnings always stay on stderr; only results are
+//! redirected.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+struct Target {
+    path: PathBuf,
+    file: File,
+}
+
+static TARGET: Mutex<Option<Target>> = Mutex::new(None);
+
+/// Send output to `path` from now on, creating its parent folders. The file
+/// is truncated, so calling this again (e.g. on every `--watch` refresh)
+/// starts it over.
+pub fn to_file(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    *lock() = Some(Target {
+        path: path.to_path_buf(),
+        file,
+    });
+    Ok(())
+}
+
+/// Whether output ends up on a terminal, which is never the case for a file
+pub fn is_terminal() -> bool {
+    lock().is_none() && std::io::stdout().is_terminal()
+}
+
+/// Write `text` as-is
+pub fn write(text: &str) -> Result<()> {
+    match lock().as_mut() {
+        Some(target) => target
+            .file
+            .write_all(text.as_bytes())
+            .with_context(|| format!("Failed to write {}", target.path.display())),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+/// Write `text` followed by a newline
+pub fn write_line(text: &str) -> Result<()> {
+    write(&format!("{}\n", text))
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Target>> {
+    TARGET.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,118 @@
+//! Interactive selection of one task from a list.
+//!
+//! `fzf` is used when it is installed. Otherwise a numbered list is shown on
+//! stderr, and typing text instead of a number narrows it with a fuzzy match.
+
+use crate::Task;
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, ErrorKind, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Whether there is a terminal to ask on: the prompt goes to stderr and the
+/// answer comes from stdin
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Let the user choose one of `tasks`, returning its index, or `None` if
+/// they cancelled
+pub fn pick(tasks: &[&Task]) -> Result<Option<usize>> {
+    if tasks.is_empty() {
+        return Ok(None);
+    }
+    let lines: Vec<String> = tasks.iter().map(|t| line(t)).collect();
+    match pick_with_fzf(&lines) {
+        Err(e) if e.kind() == ErrorKind::NotFound => pick_with_prompt(&lines),
+        result => result.context("Failed to run fzf"),
+    }
+}
+
+/// One row of the picker
+fn line(task: &Task) -> String {
+    let due = task
+        .due
+        .map(|due| format!(" due: {}", due))
+        .unwrap_or_default();
+    format!(
+        "{} {} [{}]{}",
+        task.status_symbol(),
+        task.display_name(),
+        task.status,
+        due
+    )
+}
+
+/// Run `fzf` over the rows, each prefixed with its hidden index
+fn pick_with_fzf(lines: &[String]) -> io::Result<Option<usize>> {
+    let mut child = Command::new("fzf")
+        .args(["--delimiter=\t", "--with-nth=2..", "--no-multi"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    {
+        let mut stdin = child.stdin.take().expect("fzf stdin is piped");
+        for (i, line) in lines.iter().enumerate() {
+            writeln!(stdin, "{}\t{}", i, line)?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    // fzf exits 130 on Esc / Ctrl-C and 1 when nothing matched
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let selected = String::from_utf8_lossy(&output.stdout);
+    Ok(selected
+        .split('\t')
+        .next()
+        .and_then(|i| i.trim().parse().ok()))
+}
+
+/// Ask on the terminal: a number picks, other text narrows the list, and an
+/// empty answer cancels
+fn pick_with_prompt(lines: &[String]) -> Result<Option<usize>> {
+    let mut shown: Vec<usize> = (0..lines.len()).collect();
+    let mut stdin = io::stdin().lock();
+    loop {
+        for (n, &i) in shown.iter().enumerate() {
+            eprintln!("{:>3}) {}", n + 1, lines[i]);
+        }
+        eprint!("Pick a task (number, text to narrow, empty to cancel): ");
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if stdin.read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(n) = answer.parse::<usize>() {
+            match n.checked_sub(1).and_then(|n| shown.get(n)) {
+                Some(&i) => return Ok(Some(i)),
+                None => eprintln!("No task numbered {}", n),
+            }
+            continue;
+        }
+        let narrowed: Vec<usize> = shown
+            .iter()
+            .copied()
+            .filter(|&i| fuzzy_match(answer, &lines[i]))
+            .collect();
+        match narrowed.as_slice() {
+            [] => eprintln!("Nothing matches {:?}", answer),
+            [only] => return Ok(Some(*only)),
+            _ => shown = narrowed,
+        }
+    }
+}
+
+/// Whether the characters of `query` appear in `text` in order, ignoring case
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| text.any(|c| c == q))
+}
@@ -0,0 +1,111 @@
+//! Ordering of priority values, from lowest to highest.
+//!
+//! The levels default to `low`, `medium` and `high`, and can be replaced
+//! from the config file, e.g. to add `urgent` above `high`. Values are
+//! matched case-insensitively; anything not in the list ranks with tasks that
+//! have no priority at all, below every level.
+
+use std::sync::OnceLock;
+
+/// Levels used when the config file doesn't set `priorities`
+pub const DEFAULT_LEVELS: [&str; 3] = ["low", "medium", "high"];
+
+static LEVELS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Use `levels` (lowest first) instead of [`DEFAULT_LEVELS`] for the rest of
+/// the process. Only a call made before the first ranking has any effect.
+pub fn set_levels(levels: Vec<String>) {
+    let _ = LEVELS.set(levels);
+}
+
+/// The known levels, lowest first
+pub fn levels() -> &'static [String] {
+    LEVELS.get_or_init(|| DEFAULT_LEVELS.iter().map(|l| l.to_string()).collect())
+}
+
+/// Ordinal of a priority value: 1 for the lowest level and up from there, or
+/// 0 for a missing or unknown priority
+pub fn rank(priority: Option<&str>) -> usize {
+    rank_in(levels(), priority)
+}
+
+fn rank_in(levels: &[String], priority: Option<&str>) -> usize {
+    priority
+        .and_then(|p| {
+            levels
+                .iter()
+                .position(|level| level.trim().eq_ignore_ascii_case(p.trim()))
+        })
+        .map_or(0, |i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_task_str, CaseMode, Task};
+
+    fn task(priority: Option<&str>) -> Task {
+        let priority = priority.map_or(String::new(), |p| format!("priority: {}\n", p));
+        parse_task_str(&format!("---\nstatus: open\n{}---\n", priority), "task").unwrap()
+    }
+
+    fn wanted(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn missing_and_unknown_priorities_rank_lowest() {
+        assert_eq!(rank(None), 0);
+        assert_eq!(rank(Some("someday")), 0);
+        assert_eq!(rank(Some("low")), 1);
+        assert_eq!(rank(Some("high")), 3);
+    }
+
+    #[test]
+    fn ranking_ignores_case_and_surrounding_space() {
+        assert_eq!(rank(Some("HIGH")), rank(Some("high")));
+        assert_eq!(rank(Some(" Medium ")), 2);
+    }
+
+    #[test]
+    fn a_missing_priority_never_matches_an_active_filter() {
+        let task = task(None);
+        assert!(!task.has_priority(&wanted(&["high"]), CaseMode::Insensitive));
+        assert!(task.has_priority(&[], CaseMode::Insensitive));
+    }
+
+    #[test]
+    fn filters_match_mixed_case_unless_case_sensitive() {
+        let task = task(Some("High"));
+        assert!(task.has_priority(&wanted(&["low", "HIGH"]), CaseMode::Insensitive));
+        assert!(task.has_priority(&wanted(&[" high "]), CaseMode::Insensitive));
+        assert!(!task.has_priority(&wanted(&["medium"]), CaseMode::Insensitive));
+        assert!(!task.has_priority(&wanted(&["high"]), CaseMode::Sensitive));
+        assert!(task.has_priority(&wanted(&["High"]), CaseMode::Sensitive));
+    }
+
+    #[test]
+    fn levels_order_every_value_including_unknown_ones() {
+        let mut values = vec!["high", "someday", "low", "", "medium", "HIGH"];
+        values.sort_by_key(|p| rank(Some(p)));
+        assert_eq!(values, ["someday", "", "low", "medium", "high", "HIGH"]);
+    }
+
+    #[test]
+    fn configured_levels_replace_the_defaults() {
+        let levels = wanted(&["low", "medium", "high", "urgent"]);
+        assert_eq!(rank_in(&levels, Some("Urgent")), 4);
+        assert!(rank_in(&levels, Some("urgent")) > rank_in(&levels, Some("high")));
+        assert_eq!(rank_in(&levels, Some("critical")), 0);
+        assert_eq!(rank(Some("urgent")), 0);
+    }
+
+    #[test]
+    fn minimum_priority_includes_the_level_and_above() {
+        assert!(task(Some("high")).priority_at_least("medium"));
+        assert!(task(Some("Medium")).priority_at_least("medium"));
+        assert!(!task(Some("low")).priority_at_least("medium"));
+        assert!(!task(Some("someday")).priority_at_least("low"));
+        assert!(!task(None).priority_at_least("low"));
+    }
+}
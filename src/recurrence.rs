@@ -0,0 +1,267 @@
+//! A small RRULE subset for expanding recurring tasks.
+//!
+//! Supports `FREQ=DAILY|WEEKLY|MONTHLY` with `INTERVAL`, `BYDAY` (weekly only),
+//! `COUNT` and `UNTIL`. A leading `RRULE:` and any `DTSTART` part are ignored;
+//! the task's own date anchors the series instead.
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+}
+
+impl FromStr for RRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut freq = None;
+        let mut rule = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            count: None,
+            until: None,
+        };
+
+        for part in s
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            let part = part.strip_prefix("RRULE:").unwrap_or(part);
+            if part.starts_with("DTSTART") {
+                continue;
+            }
+            let (key, value) = part
+                .split_once('=')
+                .with_context(|| format!("malformed rule part `{}`", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        other => bail!("unsupported FREQ `{}`", other),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .with_context(|| format!("invalid INTERVAL `{}`", value))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("invalid COUNT `{}`", value))?,
+                    )
+                }
+                "UNTIL" => {
+                    let date = value.get(..8).unwrap_or(value);
+                    rule.until = Some(
+                        NaiveDate::parse_from_str(date, "%Y%m%d")
+                            .with_context(|| format!("invalid UNTIL `{}`", value))?,
+                    )
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        rule.by_day.push(parse_weekday(day)?);
+                    }
+                }
+                // Anything else (WKST, BYHOUR, ...) doesn't affect which days occur
+                _ => {}
+            }
+        }
+
+        rule.freq = freq.context("missing FREQ")?;
+        Ok(rule)
+    }
+}
+
+fn parse_weekday(day: &str) -> Result<Weekday> {
+    Ok(match day.trim().to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        other => bail!("unsupported BYDAY value `{}`", other),
+    })
+}
+
+impl RRule {
+    /// Whether `date` belongs to the series anchored at `start`, ignoring COUNT/UNTIL
+    fn matches(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if date < start {
+            return false;
+        }
+        let interval = self.interval as i64;
+        match self.freq {
+            Frequency::Daily => (date - start).num_days() % interval == 0,
+            Frequency::Weekly => {
+                let week_start =
+                    |d: NaiveDate| d - chrono::Days::new(d.weekday().num_days_from_monday() as u64);
+                let weeks = (week_start(date) - week_start(start)).num_days() / 7;
+                let on_day = if self.by_day.is_empty() {
+                    date.weekday() == start.weekday()
+                } else {
+                    self.by_day.contains(&date.weekday())
+                };
+                on_day && weeks % interval == 0
+            }
+            Frequency::Monthly => {
+                let months = (date.year() - start.year()) as i64 * 12 + date.month() as i64
+                    - start.month() as i64;
+                date.day() == start.day() && months % interval == 0
+            }
+        }
+    }
+
+    /// Occurrence dates of the series anchored at `start` that fall in `from..=to`
+    pub fn occurrences(&self, start: NaiveDate, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let mut seen = 0;
+
+        for date in start.iter_days().take_while(|d| *d <= to) {
+            if self.until.is_some_and(|until| date > until) {
+                break;
+            }
+            if !self.matches(start, date) {
+                continue;
+            }
+            seen += 1;
+            if self.count.is_some_and(|count| seen > count) {
+                break;
+            }
+            if date >= from {
+                dates.push(date);
+            }
+        }
+
+        dates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn dates(list: &[&str]) -> Vec<NaiveDate> {
+        list.iter().map(|s| date(s)).collect()
+    }
+
+    fn occurrences(rule: &str, start: &str, from: &str, to: &str) -> Vec<NaiveDate> {
+        let rule: RRule = rule.parse().unwrap();
+        rule.occurrences(date(start), date(from), date(to))
+    }
+
+    #[test]
+    fn daily_steps_by_the_interval() {
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;INTERVAL=3",
+                "2026-01-01",
+                "2026-01-02",
+                "2026-01-10"
+            ),
+            dates(&["2026-01-04", "2026-01-07", "2026-01-10"])
+        );
+    }
+
+    #[test]
+    fn weekly_byday_picks_days_in_every_other_week() {
+        // 2026-01-05 is a Monday
+        assert_eq!(
+            occurrences(
+                "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+                "2026-01-05",
+                "2026-01-01",
+                "2026-01-31"
+            ),
+            dates(&["2026-01-05", "2026-01-08", "2026-01-19", "2026-01-22"])
+        );
+        assert_eq!(
+            occurrences("FREQ=WEEKLY", "2026-01-05", "2026-01-06", "2026-01-20"),
+            dates(&["2026-01-12", "2026-01-19"])
+        );
+    }
+
+    #[test]
+    fn monthly_on_the_31st_skips_shorter_months() {
+        assert_eq!(
+            occurrences("FREQ=MONTHLY", "2026-01-31", "2026-01-01", "2026-07-31"),
+            dates(&["2026-01-31", "2026-03-31", "2026-05-31", "2026-07-31"])
+        );
+    }
+
+    #[test]
+    fn count_includes_occurrences_before_the_window() {
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;COUNT=5",
+                "2026-01-01",
+                "2026-01-04",
+                "2026-01-31"
+            ),
+            dates(&["2026-01-04", "2026-01-05"])
+        );
+    }
+
+    #[test]
+    fn until_is_inclusive() {
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;UNTIL=20260103T000000Z",
+                "2026-01-01",
+                "2026-01-01",
+                "2026-01-31"
+            ),
+            dates(&["2026-01-01", "2026-01-02", "2026-01-03"])
+        );
+    }
+
+    #[test]
+    fn a_series_starting_after_the_window_has_no_occurrences() {
+        assert!(occurrences("FREQ=DAILY", "2026-02-01", "2026-01-01", "2026-01-31").is_empty());
+    }
+
+    #[test]
+    fn malformed_rules_are_refused() {
+        for rule in [
+            "INTERVAL=2",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=DAILY;UNTIL=soon",
+            "FREQ",
+        ] {
+            assert!(rule.parse::<RRule>().is_err(), "{}", rule);
+        }
+        let rule: RRule = "DTSTART:20260101\nRRULE:freq=daily;wkst=MO"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.freq, Frequency::Daily);
+    }
+}
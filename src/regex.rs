@@ -0,0 +1,514 @@
+//! A small regular expression matcher for `search --regex`.
+//!
+//! Supports literals, `.`, `^`, `$`, character classes (`[a-z]`, `[^...]`),
+//! the `\d \w \s` classes and their negations, groups, alternation and the
+//! `* + ? {n} {n,} {n,m}` quantifiers. Only "does it match anywhere" is
+//! answered, so lazy quantifiers are accepted and behave like greedy ones.
+//!
+//! Patterns are compiled to a Thompson NFA and run over the text one character
+//! at a time, so neither time nor stack depth grows with backtracking: a long
+//! line costs a pass proportional to its length times the pattern size.
+
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// One NFA instruction; `Split` and `Jump` hold indices into the program
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(lo, hi) => lo <= c && c <= hi,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => (c.is_alphanumeric() || c == '_') != negated,
+            ClassItem::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Regex> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let node = parser.alternation()?;
+        if let Some(c) = parser.peek() {
+            bail!("Invalid regex {:?}: unmatched `{}`", pattern, c);
+        }
+        let mut program = Vec::new();
+        compile(&node, &mut program);
+        program.push(Inst::Match);
+        Ok(Regex {
+            program,
+            ignore_case,
+        })
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for pos in 0..=chars.len() {
+            // A match may start at any position
+            if self.add_thread(&mut current, 0, &chars, pos) {
+                return true;
+            }
+            let Some(&c) = chars.get(pos) else {
+                break;
+            };
+            next.clear();
+            for &pc in &current.list {
+                let consumed = match &self.program[pc] {
+                    Inst::Char(p) => self.char_eq(*p, c),
+                    Inst::Any => c != '\n',
+                    Inst::Class { items, negated } => self.class_matches(items, c) != *negated,
+                    _ => false,
+                };
+                if consumed && self.add_thread(&mut next, pc + 1, &chars, pos + 1) {
+                    return true;
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+
+    /// Add `pc` and everything reachable from it without consuming a
+    /// character to `threads`, returning whether that reaches `Match`
+    fn add_thread(&self, threads: &mut Threads, pc: usize, s: &[char], pos: usize) -> bool {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if !threads.insert(pc) {
+                continue;
+            }
+            match self.program[pc] {
+                Inst::Match => return true,
+                Inst::Jump(to) => stack.push(to),
+                // Push the second branch first so the first is followed first
+                Inst::Split(first, second) => stack.extend([second, first]),
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == s.len() => stack.push(pc + 1),
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn char_eq(&self, pattern: char, c: char) -> bool {
+        pattern == c || (self.ignore_case && fold(pattern) == fold(c))
+    }
+
+    fn class_matches(&self, items: &[ClassItem], c: char) -> bool {
+        let hit = |c: char| items.iter().any(|item| item.matches(c));
+        hit(c) || (self.ignore_case && (hit(fold(c)) || hit(upper(c))))
+    }
+}
+
+/// The set of NFA states alive at one position, in the order they were added
+struct Threads {
+    list: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Threads {
+        Threads {
+            list: Vec::with_capacity(len),
+            seen: vec![false; len],
+        }
+    }
+
+    fn insert(&mut self, pc: usize) -> bool {
+        if self.seen[pc] {
+            return false;
+        }
+        self.seen[pc] = true;
+        self.list.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        for &pc in &self.list {
+            self.seen[pc] = false;
+        }
+        self.list.clear();
+    }
+}
+
+/// Append the instructions for `node` to `program`
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Empty => {}
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { items, negated } => program.push(Inst::Class {
+            items: items.clone(),
+            negated: *negated,
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(nodes) => nodes.iter().for_each(|node| compile(node, program)),
+        Node::Alt(alternatives) => {
+            let mut jumps = Vec::new();
+            let (last, rest) = alternatives.split_last().expect("alternation has branches");
+            for alternative in rest {
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alternative, program);
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            compile(last, program);
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program);
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program);
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile(node, program);
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn upper(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node> {
+        let mut alternatives = vec![self.concat()?];
+        while self.eat('|') {
+            alternatives.push(self.concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().expect("one alternative")
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node> {
+        let mut nodes = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().expect("one node"),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node> {
+        let (min, max) = match self.peek() {
+            Some(c @ ('*' | '+' | '?')) => {
+                self.bump();
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            Some('{') => match self.braces() {
+                Some(bounds) => bounds,
+                // Not a valid repetition, so `{` is a literal
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            bail!("Invalid regex: nothing to repeat");
+        }
+        // A lazy marker doesn't change whether anything matches
+        self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    /// Parse `{n}`, `{n,}`, `{,m}` or `{n,m}`, consuming it only when well-formed
+    fn braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let close = self.chars[self.pos..].iter().position(|&c| c == '}')?;
+        let inner: String = self.chars[self.pos + 1..self.pos + close].iter().collect();
+        let bounds = match inner.split_once(',') {
+            None => {
+                let n = inner.parse().ok()?;
+                (n, Some(n))
+            }
+            // An empty lower bound means zero, as in `{,3}`
+            Some((min, max)) => (
+                if min.is_empty() { 0 } else { min.parse().ok()? },
+                if max.is_empty() {
+                    None
+                } else {
+                    Some(max.parse().ok()?)
+                },
+            ),
+        };
+        if bounds.1.is_some_and(|max| max < bounds.0) {
+            return None;
+        }
+        self.pos += close + 1;
+        Some(bounds)
+    }
+
+    fn atom(&mut self) -> Result<Node> {
+        let c = self.bump().expect("atom called at end of pattern");
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // Non-capturing groups behave the same, since nothing is captured
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    bail!("Invalid regex: unclosed `(`");
+                }
+                inner
+            }
+            '[' => self.class()?,
+            '\\' => match self.escape()? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Class(item) => Node::Class {
+                    items: vec![item],
+                    negated: false,
+                },
+            },
+            '*' | '+' | '?' => bail!("Invalid regex: nothing to repeat before `{}`", c),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Escape> {
+        let c = self
+            .bump()
+            .ok_or_else(|| anyhow::anyhow!("Invalid regex: trailing `\\`"))?;
+        Ok(match c {
+            'd' => Escape::Class(ClassItem::Digit(false)),
+            'D' => Escape::Class(ClassItem::Digit(true)),
+            'w' => Escape::Class(ClassItem::Word(false)),
+            'W' => Escape::Class(ClassItem::Word(true)),
+            's' => Escape::Class(ClassItem::Space(false)),
+            'S' => Escape::Class(ClassItem::Space(true)),
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            'r' => Escape::Char('\r'),
+            c if c.is_ascii_alphanumeric() => bail!("Invalid regex: unsupported escape `\\{}`", c),
+            c => Escape::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .bump()
+                .ok_or_else(|| anyhow::anyhow!("Invalid regex: unclosed `[`"))?;
+            let lo = match c {
+                // A leading `]` is a literal
+                ']' if !first => break,
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => c,
+                    Escape::Class(item) => {
+                        items.push(item);
+                        first = false;
+                        continue;
+                    }
+                },
+                c => c,
+            };
+            first = false;
+            if self.peek() == Some('-') && !matches!(self.chars.get(self.pos + 1), None | Some(']'))
+            {
+                self.pos += 1;
+                let hi = match self.bump() {
+                    Some('\\') => match self.escape()? {
+                        Escape::Char(c) => c,
+                        Escape::Class(_) => bail!("Invalid regex: class used as range bound"),
+                    },
+                    Some(c) => c,
+                    None => bail!("Invalid regex: unclosed `[`"),
+                };
+                if hi < lo {
+                    bail!("Invalid regex: range `{}-{}` is out of order", lo, hi);
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+        Ok(Node::Class { items, negated })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Class(ClassItem),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regex;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern, false).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn matches_anywhere_in_the_text() {
+        assert!(matches("voice", "an invoice"));
+        assert!(matches("^an", "an invoice"));
+        assert!(!matches("^invoice", "an invoice"));
+        assert!(matches("ce$", "an invoice"));
+        assert!(matches("", ""));
+    }
+
+    #[test]
+    fn quantifiers_and_alternation() {
+        assert!(matches("ab*c", "ac"));
+        assert!(matches("ab+c", "abbbc"));
+        assert!(!matches("ab+c", "ac"));
+        assert!(matches("^a{2,3}$", "aaa"));
+        assert!(!matches("^a{2,3}$", "aaaa"));
+        assert!(matches("^a{2}$", "aa"));
+        assert!(matches("^(cat|dog)s?$", "dogs"));
+        assert!(!matches("^(cat|dog)s?$", "cow"));
+        assert!(matches("^(a*)*b$", "aaab"));
+        assert!(matches("x{", "x{"));
+    }
+
+    #[test]
+    fn classes_and_case() {
+        assert!(matches(r"\d{4}-\d{2}", "due 2026-10"));
+        assert!(matches("[^a-z]", "abc1"));
+        assert!(!matches("[^a-z]", "abc"));
+        assert!(Regex::new("INVOICE", true).unwrap().is_match("an invoice"));
+        assert!(!matches("INVOICE", "an invoice"));
+        assert!(!matches("a.c", "a\nc"));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(Regex::new("(ab", false).is_err());
+        assert!(Regex::new("*a", false).is_err());
+        assert!(Regex::new("[z-a]", false).is_err());
+        assert!(Regex::new(r"\q", false).is_err());
+    }
+
+    #[test]
+    fn long_lines_do_not_overflow_the_stack() {
+        let line = "a".repeat(50_000);
+        assert!(!matches("a*c", &line));
+        assert!(!matches("(a|aa)*c", &line));
+        assert!(matches("(a|aa)*c", &format!("{}c", line)));
+        assert!(matches("^a+$", &line));
+    }
+}
@@ -0,0 +1,730 @@
+//! Sorting, grouping and output rendering for task lists.
+
+use crate::vault::obsidian_uri;
+use crate::{project_name, ScanWarning, Task};
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::json;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// JSON array, pretty-printed unless `--compact` is given
+    Json,
+    /// One compact JSON object per line (NDJSON), for streaming
+    Jsonl,
+    /// One task per line: title, status, due date
+    Plain,
+    /// Aligned columns with a header row
+    Table,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+    /// GitHub-flavored markdown table, for pasting into notes
+    Md,
+    /// TOML document with one `[[task]]` table per task; unset fields are left out
+    Toml,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SortKey {
+    /// Earliest due date first
+    Due,
+    /// high > medium > low > none
+    Priority,
+    /// Oldest creation date first
+    Created,
+    /// Alphabetical by filename
+    Filename,
+    /// Most recently modified note file first
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GroupKey {
+    /// Lowercased priority
+    Priority,
+    /// Each project the task belongs to
+    Project,
+    /// Due date (day only)
+    Due,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolve `auto` against the environment
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && crate::output::is_terminal()
+            }
+        }
+    }
+}
+
+/// How task lists are rendered
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub format: OutputFormat,
+    /// Highlight plain and table rows by urgency with ANSI colors
+    pub color: bool,
+    /// Show plain and table dates relative to today instead of as ISO dates
+    pub relative_dates: bool,
+    /// Print `json` output without whitespace instead of pretty-printed
+    pub compact: bool,
+    /// Add computed fields such as `days_until_due` to JSON output
+    pub with_computed: bool,
+    /// Only these JSON fields, in this order; see [`crate::schema::field_names`]
+    pub fields: Option<Vec<String>>,
+    /// Vault named in `obsidian_uri` links, instead of the one found on disk
+    pub vault_name: Option<String>,
+    /// Add the note body after the frontmatter to JSON output
+    pub include_body: bool,
+    /// The moment overdue, due-today and relative dates are judged at, as
+    /// wall-clock time in the configured zone
+    pub now: NaiveDateTime,
+}
+
+/// A task serialized with its computed fields appended
+#[derive(serde::Serialize)]
+pub struct ComputedTask<'a> {
+    #[serde(flatten)]
+    pub task: &'a Task,
+    pub days_until_due: Option<i64>,
+    pub obsidian_uri: Option<String>,
+}
+
+impl<'a> ComputedTask<'a> {
+    pub fn new(task: &'a Task, options: &RenderOptions) -> ComputedTask<'a> {
+        ComputedTask {
+            task,
+            days_until_due: task.days_until_due(options.now.date()),
+            obsidian_uri: obsidian_uri(&task.path, options.vault_name.as_deref()),
+        }
+    }
+}
+
+/// Selected fields of a task, serialized as an object in the order requested
+pub struct ProjectedTask(Vec<(String, serde_json::Value)>);
+
+impl serde::Serialize for ProjectedTask {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// A task serialized with its note body appended
+#[derive(serde::Serialize)]
+pub struct WithBody<'a, T> {
+    #[serde(flatten)]
+    pub task: T,
+    pub body: &'a str,
+}
+
+/// A task in the shape JSON output asks for
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub enum TaskJson<'a> {
+    Task(&'a Task),
+    Computed(ComputedTask<'a>),
+    Projected(ProjectedTask),
+    TaskWithBody(WithBody<'a, &'a Task>),
+    ComputedWithBody(WithBody<'a, ComputedTask<'a>>),
+}
+
+impl<'a> TaskJson<'a> {
+    /// Apply `--with-computed`, `--include-body` and `--fields`. Computed
+    /// fields and `body` can be picked with `--fields` without also passing
+    /// the flag that adds them.
+    pub fn new(task: &'a Task, options: &RenderOptions) -> Result<TaskJson<'a>> {
+        let Some(fields) = &options.fields else {
+            let body = task.body_text();
+            return Ok(match (options.with_computed, options.include_body) {
+                (false, false) => TaskJson::Task(task),
+                (true, false) => TaskJson::Computed(ComputedTask::new(task, options)),
+                (false, true) => TaskJson::TaskWithBody(WithBody { task, body }),
+                (true, true) => TaskJson::ComputedWithBody(WithBody {
+                    task: ComputedTask::new(task, options),
+                    body,
+                }),
+            });
+        };
+        let serde_json::Value::Object(mut map) =
+            serde_json::to_value(ComputedTask::new(task, options))?
+        else {
+            unreachable!("tasks serialize as objects");
+        };
+        map.insert("body".to_string(), task.body_text().into());
+        let picked = fields
+            .iter()
+            .map(|f| (f.clone(), map.remove(f).unwrap_or_default()))
+            .collect();
+        Ok(TaskJson::Projected(ProjectedTask(picked)))
+    }
+}
+
+/// JSON output wrapped with when and how it was produced, for `--envelope`
+#[derive(serde::Serialize)]
+pub struct Envelope<'a, T> {
+    /// RFC 3339 timestamp in the configured timezone
+    pub generated_at: String,
+    /// The subcommand that produced the tasks, e.g. `overdue`
+    pub query: &'a str,
+    /// Number of tasks, across all groups when grouped
+    pub count: usize,
+    /// The task array, or an object of arrays with `--group-by`
+    pub tasks: T,
+    /// Notes that were skipped because they couldn't be read or parsed
+    pub warnings: &'a [ScanWarning],
+}
+
+/// Tasks to put in an [`Envelope`]: a plain list, or groups of them
+pub enum Listing<'a, 'b> {
+    List(&'b [&'a Task]),
+    Groups(&'b BTreeMap<String, Vec<&'a Task>>),
+}
+
+/// `listing` as an enveloped JSON object. The count is of distinct tasks, so
+/// one filed under several groups counts once.
+pub fn render_envelope(
+    listing: Listing,
+    query: &str,
+    warnings: &[ScanWarning],
+    options: &RenderOptions,
+) -> Result<String> {
+    let (count, tasks) = match listing {
+        Listing::List(tasks) => (
+            tasks.len(),
+            serde_json::to_value(task_jsons(tasks, options)?)?,
+        ),
+        Listing::Groups(groups) => {
+            let distinct: HashSet<*const Task> = groups
+                .values()
+                .flatten()
+                .map(|&t| t as *const Task)
+                .collect();
+            let groups = groups
+                .iter()
+                .map(|(name, tasks)| Ok((name.as_str(), task_jsons(tasks, options)?)))
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            (distinct.len(), serde_json::to_value(groups)?)
+        }
+    };
+    let envelope = Envelope {
+        generated_at: crate::clock::now_with_offset(crate::clock::timezone())
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        query,
+        count,
+        tasks,
+        warnings,
+    };
+    to_json(&envelope, options.compact)
+}
+
+fn task_jsons<'a>(tasks: &[&'a Task], options: &RenderOptions) -> Result<Vec<TaskJson<'a>>> {
+    tasks.iter().map(|t| TaskJson::new(t, options)).collect()
+}
+
+/// `value` as a TOML document, with `null` fields left out
+fn to_toml<T: serde::Serialize>(value: &T) -> Result<String> {
+    crate::toml::to_string(&serde_json::to_value(value)?)
+}
+
+/// `value` as JSON, pretty-printed unless `compact`
+pub fn to_json<T: serde::Serialize + ?Sized>(value: &T, compact: bool) -> Result<String> {
+    Ok(if compact {
+        serde_json::to_string(value)?
+    } else {
+        serde_json::to_string_pretty(value)?
+    })
+}
+
+/// ANSI style for a row as of `now`: red when overdue, yellow when due today,
+/// dim green when done
+fn urgency_style(task: &Task, now: NaiveDateTime) -> Option<&'static str> {
+    if task.is_overdue(now) {
+        Some("\x1b[31m")
+    } else if task.is_done() {
+        Some("\x1b[2;32m")
+    } else if task.is_cancelled() {
+        Some("\x1b[2m")
+    } else if task.is_due_today(now.date()) {
+        Some("\x1b[33m")
+    } else {
+        None
+    }
+}
+
+fn paint(text: String, task: &Task, options: &RenderOptions) -> String {
+    match urgency_style(task, options.now) {
+        Some(style) if options.color => format!("{}{}\x1b[0m", style, text),
+        _ => text,
+    }
+}
+
+/// Compare optional values, keeping `None` last whichever direction is requested
+fn cmp_present_first<T: Ord>(a: Option<T>, b: Option<T>, reverse: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if reverse => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn cmp_by_key(a: &Task, b: &Task, key: SortKey, reverse: bool) -> Ordering {
+    let flip = |order: Ordering| if reverse { order.reverse() } else { order };
+    match key {
+        SortKey::Due => cmp_present_first(a.due, b.due, reverse),
+        SortKey::Created => cmp_present_first(a.date_created, b.date_created, reverse),
+        SortKey::Modified => cmp_present_first(a.modified, b.modified, !reverse),
+        // Highest first; a rank of 0 means no (or an unknown) priority
+        SortKey::Priority => {
+            let rank = |t: &Task| Some(t.priority_rank()).filter(|&rank| rank > 0);
+            cmp_present_first(rank(a), rank(b), !reverse)
+        }
+        SortKey::Filename => flip(a.filename.cmp(&b.filename)),
+    }
+}
+
+/// Sort by each of `keys` in turn, later keys breaking ties in earlier ones.
+/// `reverse` flips every key, while tasks missing a value still sort last at
+/// each level. Remaining ties fall back to the file path, so the order is the
+/// same from run to run whatever order the filesystem lists notes in.
+pub fn sort_tasks(tasks: &mut [&Task], keys: &[SortKey], reverse: bool) {
+    tasks.sort_by(|a, b| {
+        keys.iter()
+            .map(|&key| cmp_by_key(a, b, key, reverse))
+            .find(|order| order.is_ne())
+            .unwrap_or_else(|| a.path.cmp(&b.path))
+    });
+}
+
+/// Minutes as `1h 30m`, `45m` or `2h`
+pub fn format_minutes(minutes: u64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// `date` relative to `today`: `today`, `tomorrow`, `yesterday`, `in 3 days`
+/// or `3 days ago`
+pub fn humanize_date(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        n if n > 0 => format!("in {} days", n),
+        n => format!("{} days ago", -n),
+    }
+}
+
+/// Due date for plain and table rows. Relative due dates read as a deadline,
+/// so an open task past due shows `3 days overdue` rather than `3 days ago`.
+fn format_due(task: &Task, options: &RenderOptions) -> String {
+    let Some(due) = task.due else {
+        return "-".to_string();
+    };
+    if !options.relative_dates {
+        return due.to_string();
+    }
+    let today = options.now.date();
+    let days = (due.date() - today).num_days();
+    let when = match days {
+        -1 if task.is_open() => "1 day overdue".to_string(),
+        n if n < -1 && task.is_open() => format!("{} days overdue", -n),
+        _ => humanize_date(due.date(), today),
+    };
+    match due.time() {
+        Some(time) => format!("{} {}", when, time.format("%H:%M")),
+        None => when,
+    }
+}
+
+/// Render a list of tasks in the requested output format. Color is only ever
+/// applied to the plain and table formats.
+pub fn render_tasks(tasks: &[&Task], options: &RenderOptions) -> Result<String> {
+    let output = match options.format {
+        OutputFormat::Json => to_json(&task_jsons(tasks, options)?, options.compact)?,
+        OutputFormat::Jsonl => json_lines(&task_jsons(tasks, options)?)?,
+        OutputFormat::Plain => tasks
+            .iter()
+            .map(|t| {
+                let line = format!(
+                    "{} {} [{}] due: {}",
+                    t.status_symbol(),
+                    t.display_name(),
+                    t.status,
+                    format_due(t, options)
+                );
+                paint(line, t, options)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Csv => render_csv(tasks),
+        OutputFormat::Md => render_markdown(tasks),
+        OutputFormat::Toml => to_toml(&json!({ "task": task_jsons(tasks, options)? }))?,
+        OutputFormat::Table => {
+            let header = ["TASK", "STATUS", "PRIORITY", "DUE"];
+            let rows: Vec<[String; 4]> = tasks
+                .iter()
+                .map(|t| {
+                    [
+                        format!("{} {}", t.status_symbol(), t.display_name()),
+                        t.status.clone(),
+                        t.priority.clone().unwrap_or_else(|| "-".to_string()),
+                        format_due(t, options),
+                    ]
+                })
+                .collect();
+
+            let mut widths = header.map(|h| h.len());
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+
+            let format_row = |cells: &[String]| {
+                cells
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                    .collect::<Vec<_>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            };
+
+            let mut lines = vec![format_row(&header.map(String::from))];
+            lines.extend(
+                rows.iter()
+                    .zip(tasks)
+                    .map(|(row, t)| paint(format_row(row), t, options)),
+            );
+            lines.join("\n")
+        }
+    };
+
+    Ok(output)
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+const CSV_HEADER: &str = "filename,status,priority,due,completed,tags,projects,title";
+
+fn csv_row(task: &Task) -> String {
+    let fields = [
+        task.filename.clone(),
+        task.status.clone(),
+        task.priority.clone().unwrap_or_default(),
+        task.due.map(|d| d.to_string()).unwrap_or_default(),
+        task.completed_date
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        task.tags.join(";"),
+        task.projects.join(";"),
+        task.display_name().to_string(),
+    ];
+    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    row.join(",")
+}
+
+fn render_csv(tasks: &[&Task]) -> String {
+    let mut lines = vec![CSV_HEADER.to_string()];
+    lines.extend(tasks.iter().map(|t| csv_row(t)));
+    lines.join("\n")
+}
+
+/// Grouped CSV stays one table, with the group in a leading `group` column
+/// instead of headings between sections
+fn render_csv_groups(groups: &BTreeMap<String, Vec<&Task>>) -> String {
+    let mut lines = vec![format!("group,{}", CSV_HEADER)];
+    for (name, tasks) in groups {
+        for task in tasks {
+            lines.push(format!("{},{}", csv_field(name), csv_row(task)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Escape a markdown table cell so pipes and line breaks can't split the row
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+fn render_markdown(tasks: &[&Task]) -> String {
+    let mut lines = vec![
+        "| Title | Status | Priority | Due |".to_string(),
+        "| --- | --- | --- | --- |".to_string(),
+    ];
+    for task in tasks {
+        let cells = [
+            task.display_name().to_string(),
+            task.status.clone(),
+            task.priority.clone().unwrap_or_default(),
+            task.due.map(|d| d.to_string()).unwrap_or_default(),
+        ];
+        let cells: Vec<String> = cells.iter().map(|c| markdown_cell(c)).collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+/// Group tasks under each of their projects; tasks without one land in `(none)`
+pub fn group_by_project(tasks: &[Task]) -> BTreeMap<String, Vec<&Task>> {
+    let tasks: Vec<&Task> = tasks.iter().collect();
+    group_tasks(&tasks, GroupKey::Project)
+}
+
+/// Group tasks by `key`, keeping their order within each group. Tasks without
+/// a value land in `(none)`; a task in several projects appears under each.
+pub fn group_tasks<'a>(tasks: &[&'a Task], key: GroupKey) -> BTreeMap<String, Vec<&'a Task>> {
+    let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    for &task in tasks {
+        let names: Vec<String> = match key {
+            GroupKey::Priority => task
+                .priority
+                .as_deref()
+                .map(|p| p.trim().to_lowercase())
+                .into_iter()
+                .collect(),
+            GroupKey::Project => task
+                .projects
+                .iter()
+                .map(|p| project_name(p).to_string())
+                .collect(),
+            GroupKey::Due => task
+                .due
+                .map(|due| due.date().format("%Y-%m-%d").to_string())
+                .into_iter()
+                .collect(),
+        };
+        if names.is_empty() {
+            groups.entry("(none)".to_string()).or_default().push(task);
+        }
+        for name in names {
+            groups.entry(name).or_default().push(task);
+        }
+    }
+    groups
+}
+
+/// Each item as compact single-line JSON, one per line
+pub fn json_lines<T: serde::Serialize>(items: &[T]) -> Result<String> {
+    let lines = items
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Grouped items as one JSON object keyed by group, or one `{"group", "tasks"}`
+/// line per group for jsonl
+fn groups_json<K, T>(groups: &BTreeMap<K, Vec<T>>, options: &RenderOptions) -> Result<String>
+where
+    K: AsRef<str> + serde::Serialize,
+    T: serde::Serialize,
+{
+    if let OutputFormat::Json = options.format {
+        return to_json(groups, options.compact);
+    }
+    #[derive(serde::Serialize)]
+    struct GroupLine<'a, T> {
+        group: &'a str,
+        tasks: &'a [T],
+    }
+    let lines: Vec<_> = groups
+        .iter()
+        .map(|(name, tasks)| GroupLine {
+            group: name.as_ref(),
+            tasks,
+        })
+        .collect();
+    json_lines(&lines)
+}
+
+/// Render grouped tasks: a JSON object keyed by group, or headed sections otherwise
+pub fn render_groups(
+    groups: &BTreeMap<String, Vec<&Task>>,
+    options: &RenderOptions,
+) -> Result<String> {
+    if let OutputFormat::Json | OutputFormat::Jsonl = options.format {
+        let groups = groups
+            .iter()
+            .map(|(name, tasks)| Ok((name.as_str(), task_jsons(tasks, options)?)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        return groups_json(&groups, options);
+    }
+    if let OutputFormat::Toml = options.format {
+        let groups = groups
+            .iter()
+            .map(|(name, tasks)| {
+                Ok((
+                    name.as_str(),
+                    json!({ "task": task_jsons(tasks, options)? }),
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        return to_toml(&groups);
+    }
+    if let OutputFormat::Csv = options.format {
+        return Ok(render_csv_groups(groups));
+    }
+
+    let mut sections = Vec::new();
+    for (name, tasks) in groups {
+        sections.push(format!("## {}\n{}", name, render_tasks(tasks, options)?));
+    }
+    Ok(sections.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_task_str;
+
+    fn task(filename: &str, frontmatter: &str) -> Task {
+        let mut task = parse_task_str(
+            &format!("---\nstatus: open\n{}\n---\n", frontmatter),
+            filename,
+        )
+        .unwrap();
+        task.path = std::path::PathBuf::from(format!("{}.md", filename));
+        task
+    }
+
+    fn sorted(tasks: &[Task], keys: &[SortKey], reverse: bool) -> Vec<String> {
+        let mut refs: Vec<&Task> = tasks.iter().collect();
+        sort_tasks(&mut refs, keys, reverse);
+        refs.iter().map(|t| t.filename.clone()).collect()
+    }
+
+    #[test]
+    fn tasks_without_a_priority_sort_last_both_ways() {
+        let tasks = [
+            task("none", ""),
+            task("low", "priority: low"),
+            task("high", "priority: High"),
+            task("odd", "priority: someday"),
+        ];
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Priority], false),
+            ["high", "low", "none", "odd"]
+        );
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Priority], true),
+            ["low", "high", "none", "odd"]
+        );
+    }
+
+    #[test]
+    fn tasks_without_a_due_date_sort_last_both_ways() {
+        let tasks = [
+            task("undated", ""),
+            task("later", "due: 2026-10-20"),
+            task("sooner", "due: 2026-10-10"),
+        ];
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Due], false),
+            ["sooner", "later", "undated"]
+        );
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Due], true),
+            ["later", "sooner", "undated"]
+        );
+    }
+
+    #[test]
+    fn later_keys_break_ties_then_the_path() {
+        let tasks = [
+            task("b", "priority: high\ndue: 2026-10-20"),
+            task("a", "priority: high\ndue: 2026-10-20"),
+            task("c", "priority: high\ndue: 2026-10-10"),
+        ];
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Priority, SortKey::Due], false),
+            ["c", "a", "b"]
+        );
+    }
+
+    #[test]
+    fn the_longest_overdue_task_sorts_first() {
+        let tasks = [
+            task("yesterday", "due: 2000-01-02"),
+            task("oldest", "due: 1999-12-01"),
+            task("morning", "due: 2000-01-01T09:00"),
+            task("day", "due: 2000-01-01"),
+        ];
+        let now = NaiveDate::from_ymd_opt(2000, 1, 3)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert!(tasks.iter().all(|t| t.is_overdue(now)));
+        assert_eq!(
+            sorted(&tasks, &[SortKey::Due], false),
+            ["oldest", "morning", "day", "yesterday"]
+        );
+    }
+
+    #[test]
+    fn csv_puts_the_free_text_title_last() {
+        let tasks = [
+            task("plain", "priority: high\ntags: [a, b]"),
+            task("quoted", "title: \"Call, then \\\"write\\\"\""),
+        ];
+        let refs: Vec<&Task> = tasks.iter().collect();
+        assert_eq!(
+            render_csv(&refs),
+            "filename,status,priority,due,completed,tags,projects,title\n\
+             plain,open,high,,,a;b,,plain\n\
+             quoted,open,,,,,,\"Call, then \"\"write\"\"\""
+        );
+    }
+
+    #[test]
+    fn grouped_csv_is_one_table_with_a_group_column() {
+        let tasks = [task("a", "priority: high"), task("b", "")];
+        let mut groups = BTreeMap::new();
+        groups.insert("high, urgent".to_string(), vec![&tasks[0]]);
+        groups.insert("none".to_string(), vec![&tasks[1]]);
+        assert_eq!(
+            render_csv_groups(&groups),
+            "group,filename,status,priority,due,completed,tags,projects,title\n\
+             \"high, urgent\",a,open,high,,,,,a\n\
+             none,b,open,,,,,,b"
+        );
+    }
+}
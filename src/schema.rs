@@ -0,0 +1,193 @@
+//! JSON Schema for the task objects printed by the JSON output formats.
+//!
+//! The schema is written out by hand and has to follow the serialized shape of
+//! [`Task`](crate::Task): every field is always present, and optional fields
+//! are `null` when unset. Update it whenever a serialized field changes.
+
+use serde_json::{json, Value};
+
+/// A nullable string in `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS[.fff][±HH:MM]`
+/// form; values written with a UTC offset keep it
+fn task_date(description: &str) -> Value {
+    json!({
+        "description": description,
+        "type": ["string", "null"],
+        "pattern": r"^\d{4}-\d{2}-\d{2}(T\d{2}:\d{2}:\d{2}(\.\d+)?([+-]\d{2}:\d{2})?)?$"
+    })
+}
+
+fn string_list(description: &str) -> Value {
+    json!({
+        "description": description,
+        "type": "array",
+        "items": { "type": "string" }
+    })
+}
+
+/// JSON Schema (draft 2020-12) for one task object
+pub fn task_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Task",
+        "description": "A TaskNote, as printed by `--format json` and `--format jsonl`",
+        "type": "object",
+        "properties": {
+            "filename": {
+                "description": "File stem of the note",
+                "type": "string"
+            },
+            "title": {
+                "description": "Frontmatter `title`, if set",
+                "type": ["string", "null"]
+            },
+            "status": {
+                "description": "Raw `status` value, e.g. `open`, `in-progress` or `done`",
+                "type": "string"
+            },
+            "priority": {
+                "description": "Raw `priority` value, e.g. `high`, `medium` or `low`",
+                "type": ["string", "null"]
+            },
+            "dateCreated": task_date("When the note was created"),
+            "tags": string_list("Tags, with nested tags written as `parent/child`"),
+            "projects": string_list("Projects, usually `[[wikilinks]]` to project notes"),
+            "contexts": string_list("GTD-style contexts such as `@home`"),
+            "due": task_date("Due date, optionally with a time of day"),
+            "scheduled": task_date("Scheduled date, optionally with a time of day"),
+            "start": {
+                "description": "Earliest day work on the task can begin",
+                "type": ["string", "null"],
+                "format": "date"
+            },
+            "completedDate": {
+                "description": "Day the task was completed",
+                "type": ["string", "null"],
+                "format": "date"
+            },
+            "recurrence": {
+                "description": "RRULE-style recurrence such as `FREQ=WEEKLY;BYDAY=MO`",
+                "type": ["string", "null"]
+            },
+            "timeEstimate": {
+                "description": "Estimated effort in minutes",
+                "type": ["integer", "null"],
+                "minimum": 0
+            },
+            "taskSourceType": {
+                "description": "Where the task came from, e.g. `taskNotes` or `inline`",
+                "type": ["string", "null"]
+            },
+            "dependsOn": string_list("Filenames or wikilinks of tasks that must be finished first"),
+            "days_until_due": {
+                "description": "Computed with `--with-computed`: days from today to `due`, negative when overdue",
+                "type": ["integer", "null"]
+            },
+            "obsidian_uri": {
+                "description": "Computed with `--with-computed`: an `obsidian://open` link to the note, null when the vault is unknown",
+                "type": ["string", "null"]
+            },
+            "body": {
+                "description": "With `--include-body`: the note content after the frontmatter, without trailing newlines",
+                "type": "string"
+            }
+        },
+        "required": [
+            "filename", "title", "status", "priority", "dateCreated", "tags",
+            "projects", "contexts", "due", "scheduled", "start", "completedDate",
+            "recurrence", "timeEstimate", "taskSourceType", "dependsOn"
+        ],
+        "additionalProperties": false
+    })
+}
+
+/// The schema for task objects cut down to `fields`, as printed with
+/// `--fields`: only those properties are allowed and required. An empty list
+/// keeps every field, like [`task_schema`].
+pub fn task_schema_for(fields: &[String]) -> Value {
+    let mut schema = task_schema();
+    if fields.is_empty() {
+        return schema;
+    }
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.retain(|name, _| fields.contains(name));
+    }
+    if let Some(required) = schema["required"].as_array_mut() {
+        required.retain(|name| {
+            name.as_str()
+                .is_some_and(|name| fields.iter().any(|f| f == name))
+        });
+    }
+    schema
+}
+
+/// Every field name a task object can have in JSON output, including computed ones
+pub fn field_names() -> Vec<String> {
+    task_schema()["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(value: &Value) -> Vec<String> {
+        match value {
+            Value::Object(map) => map.keys().cloned().collect(),
+            Value::Array(items) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn every_required_field_has_a_property() {
+        let schema = task_schema();
+        let properties = names(&schema["properties"]);
+        for name in names(&schema["required"]) {
+            assert!(properties.contains(&name), "{}", name);
+        }
+        assert_eq!(field_names(), properties);
+    }
+
+    #[test]
+    fn selected_fields_are_the_only_ones_allowed_and_required() {
+        let fields = [
+            "due".to_string(),
+            "filename".to_string(),
+            "days_until_due".to_string(),
+        ];
+        let schema = task_schema_for(&fields);
+        assert_eq!(
+            names(&schema["properties"]),
+            ["days_until_due", "due", "filename"]
+        );
+        assert_eq!(names(&schema["required"]), ["filename", "due"]);
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(task_schema_for(&[]), task_schema());
+    }
+
+    #[test]
+    fn date_patterns_match_every_printed_form() {
+        let pattern = task_schema()["properties"]["dateCreated"]["pattern"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let pattern = crate::regex::Regex::new(&pattern, false).unwrap();
+        for date in [
+            "2026-01-30",
+            "2026-01-30T08:18:00",
+            "2026-01-30T08:18:47.998",
+            "2026-01-30T08:18:47.998-05:00",
+            "2026-01-30T08:18:47Z",
+        ] {
+            let printed = date.parse::<crate::TaskDate>().unwrap().to_string();
+            assert!(pattern.is_match(&printed), "{}", printed);
+        }
+        assert!(!pattern.is_match("2026-01-30T08:18"));
+    }
+}
@@ -0,0 +1,140 @@
+//! Aggregate numbers describing a whole vault.
+
+use crate::render::format_minutes;
+use crate::{project_name, total_effort, StatusKind, Task};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Summary counts over a set of tasks
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub done: usize,
+    pub cancelled: usize,
+    pub pending: usize,
+    pub overdue: usize,
+    pub due_today: usize,
+    pub completed_today: usize,
+    /// Sum of `timeEstimate` minutes
+    pub effort_minutes: u64,
+    pub by_priority: BTreeMap<String, usize>,
+    pub by_project: BTreeMap<String, usize>,
+    /// Completion of each project; tasks without a project are left out
+    pub project_progress: BTreeMap<String, ProjectProgress>,
+}
+
+/// How far along a project is
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectProgress {
+    pub total: usize,
+    pub done: usize,
+    pub cancelled: usize,
+    /// `done` out of the tasks that weren't cancelled, from 0 to 1
+    pub ratio: f64,
+}
+
+impl ProjectProgress {
+    /// A `[#####-----] 50% (2/4)` bar, `width` characters wide between the brackets
+    pub fn render_bar(&self, width: usize) -> String {
+        let filled = ((self.ratio * width as f64).round() as usize).min(width);
+        format!(
+            "[{}{}] {:.0}% ({}/{})",
+            "#".repeat(filled),
+            "-".repeat(width - filled),
+            self.ratio * 100.0,
+            self.done,
+            self.total - self.cancelled
+        )
+    }
+}
+
+impl Stats {
+    /// Compute all counts in a single pass over `tasks`, judging overdue and
+    /// today's tasks as of `now`
+    pub fn compute(tasks: &[Task], now: NaiveDateTime) -> Stats {
+        let mut stats = Stats::default();
+
+        for task in tasks {
+            stats.total += 1;
+            match task.status_kind() {
+                StatusKind::Done => stats.done += 1,
+                StatusKind::Cancelled => stats.cancelled += 1,
+                StatusKind::Open => stats.pending += 1,
+            }
+            if task.is_overdue(now) {
+                stats.overdue += 1;
+            }
+            if task.is_due_today(now.date()) {
+                stats.due_today += 1;
+            }
+            if task.is_completed_today(now.date()) {
+                stats.completed_today += 1;
+            }
+
+            let priority = task
+                .priority
+                .as_deref()
+                .map(|p| p.trim().to_lowercase())
+                .unwrap_or_else(|| "(none)".to_string());
+            *stats.by_priority.entry(priority).or_default() += 1;
+
+            if task.projects.is_empty() {
+                *stats.by_project.entry("(none)".to_string()).or_default() += 1;
+            }
+            for project in &task.projects {
+                let name = project_name(project).to_string();
+                *stats.by_project.entry(name.clone()).or_default() += 1;
+                let progress = stats.project_progress.entry(name).or_default();
+                progress.total += 1;
+                match task.status_kind() {
+                    StatusKind::Done => progress.done += 1,
+                    StatusKind::Cancelled => progress.cancelled += 1,
+                    StatusKind::Open => {}
+                }
+            }
+        }
+
+        for progress in stats.project_progress.values_mut() {
+            let counted = progress.total - progress.cancelled;
+            if counted > 0 {
+                progress.ratio = progress.done as f64 / counted as f64;
+            }
+        }
+        stats.effort_minutes = total_effort(tasks);
+        stats
+    }
+
+    /// Human-readable `key: value` listing used by the plain and table formats
+    pub fn render_plain(&self) -> String {
+        let mut lines = vec![
+            format!("total: {}", self.total),
+            format!("done: {}", self.done),
+            format!("cancelled: {}", self.cancelled),
+            format!("pending: {}", self.pending),
+            format!("overdue: {}", self.overdue),
+            format!("due today: {}", self.due_today),
+            format!("completed today: {}", self.completed_today),
+            format!("effort: {}", format_minutes(self.effort_minutes)),
+            "by priority:".to_string(),
+        ];
+        lines.extend(
+            self.by_priority
+                .iter()
+                .map(|(k, v)| format!("  {}: {}", k, v)),
+        );
+        lines.push("by project:".to_string());
+        lines.extend(
+            self.by_project
+                .iter()
+                .map(|(k, v)| format!("  {}: {}", k, v)),
+        );
+        lines.push("project progress:".to_string());
+        lines.extend(
+            self.project_progress
+                .iter()
+                .map(|(k, p)| format!("  {}: {}", k, p.render_bar(10))),
+        );
+        lines.join("\n")
+    }
+}
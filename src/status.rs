@@ -0,0 +1,258 @@
+//! Classification of free-form `status` strings into open, done and cancelled,
+//! and the checkbox symbols shown for them.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// What a task's `status` means for filtering and counting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Open,
+    Done,
+    Cancelled,
+}
+
+/// The status strings treated as done or cancelled. Matching is
+/// case-insensitive, and a status listed as both counts as done.
+#[derive(Debug, Clone)]
+pub struct Statuses {
+    pub done: Vec<String>,
+    pub cancelled: Vec<String>,
+}
+
+impl Default for Statuses {
+    fn default() -> Statuses {
+        Statuses {
+            done: vec!["done".into(), "completed".into(), "x".into()],
+            cancelled: vec!["cancelled".into(), "canceled".into()],
+        }
+    }
+}
+
+impl Statuses {
+    /// The defaults plus any extra done and cancelled statuses
+    pub fn with_extra(done: &[String], cancelled: &[String]) -> Statuses {
+        let mut statuses = Statuses::default();
+        statuses.done.extend(done.iter().cloned());
+        statuses.cancelled.extend(cancelled.iter().cloned());
+        statuses
+    }
+
+    pub fn classify(&self, status: &str) -> StatusKind {
+        let status = status.trim();
+        if self
+            .done
+            .iter()
+            .any(|s| s.trim().eq_ignore_ascii_case(status))
+        {
+            StatusKind::Done
+        } else if self
+            .cancelled
+            .iter()
+            .any(|s| s.trim().eq_ignore_ascii_case(status))
+        {
+            StatusKind::Cancelled
+        } else {
+            StatusKind::Open
+        }
+    }
+
+    /// A done or cancelled status that `status` looks like a typo of: within a
+    /// small edit distance, ignoring case and surrounding whitespace. Exact
+    /// matches and statuses shorter than three letters are never reported.
+    pub fn near_miss(&self, status: &str) -> Option<&str> {
+        if self.classify(status) != StatusKind::Open {
+            return None;
+        }
+        let status = status.trim().to_lowercase();
+        self.done
+            .iter()
+            .chain(&self.cancelled)
+            .map(|known| known.trim())
+            .filter(|known| known.chars().count() >= 3)
+            .map(|known| (known, edit_distance(&status, &known.to_lowercase())))
+            .filter(|&(known, distance)| {
+                let allowed = if known.chars().count() <= 5 { 1 } else { 2 };
+                distance <= allowed
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(known, _)| known)
+    }
+}
+
+/// Levenshtein distance between two strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Open statuses meaning work has begun, matched ignoring case
+pub const IN_PROGRESS: [&str; 3] = ["in-progress", "doing", "/"];
+
+/// Whether a raw `status` is one of [`IN_PROGRESS`]
+pub fn is_in_progress(status: &str) -> bool {
+    IN_PROGRESS
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(status.trim()))
+}
+
+/// The canonical spelling of a raw `status` of the given kind: `done`,
+/// `cancelled`, `in-progress` for any of [`IN_PROGRESS`], or else `open`
+pub fn canonical(status: &str, kind: StatusKind) -> &'static str {
+    match kind {
+        StatusKind::Open if is_in_progress(status) => "in-progress",
+        StatusKind::Open => "open",
+        StatusKind::Done => "done",
+        StatusKind::Cancelled => "cancelled",
+    }
+}
+
+/// Obsidian-style checkboxes shown before plain and table rows, keyed by raw
+/// status or by `open`, `done` and `cancelled` for the rest of each kind
+pub const DEFAULT_SYMBOLS: [(&str, &str); 4] = [
+    ("open", "[ ]"),
+    ("in-progress", "[/]"),
+    ("done", "[x]"),
+    ("cancelled", "[-]"),
+];
+
+static SYMBOLS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Layer `overrides` (status to symbol) over [`DEFAULT_SYMBOLS`] for the rest
+/// of the process. Only a call made before the first lookup has any effect.
+pub fn set_symbols(overrides: HashMap<String, String>) {
+    let mut symbols = default_symbols();
+    symbols.extend(
+        overrides
+            .into_iter()
+            .map(|(status, symbol)| (status.trim().to_lowercase(), symbol)),
+    );
+    let _ = SYMBOLS.set(symbols);
+}
+
+fn default_symbols() -> HashMap<String, String> {
+    DEFAULT_SYMBOLS
+        .iter()
+        .map(|&(status, symbol)| (status.to_string(), symbol.to_string()))
+        .collect()
+}
+
+/// The symbol for a raw `status` of the given kind: its own entry if it has
+/// one, otherwise the entry for its kind (with every [`IN_PROGRESS`] status
+/// sharing the `in-progress` one)
+pub fn symbol(status: &str, kind: StatusKind) -> &'static str {
+    let symbols = SYMBOLS.get_or_init(default_symbols);
+    let fallback = match kind {
+        StatusKind::Open if is_in_progress(status) => "in-progress",
+        StatusKind::Open => "open",
+        StatusKind::Done => "done",
+        StatusKind::Cancelled => "cancelled",
+    };
+    symbols
+        .get(&status.trim().to_lowercase())
+        .or_else(|| symbols.get(fallback))
+        .map_or("", String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_task_str;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn extra_statuses_are_merged_with_the_defaults() {
+        let statuses = Statuses::with_extra(&strings(&["archived"]), &strings(&["dropped"]));
+        assert_eq!(statuses.classify("Archived"), StatusKind::Done);
+        assert_eq!(statuses.classify(" done "), StatusKind::Done);
+        assert_eq!(statuses.classify("dropped"), StatusKind::Cancelled);
+        assert_eq!(statuses.classify("cancelled"), StatusKind::Cancelled);
+        assert_eq!(statuses.classify("waiting"), StatusKind::Open);
+        assert_eq!(Statuses::default().classify("archived"), StatusKind::Open);
+    }
+
+    #[test]
+    fn a_status_listed_as_both_counts_as_done() {
+        let statuses = Statuses::with_extra(&strings(&["cancelled"]), &[]);
+        assert_eq!(statuses.classify("cancelled"), StatusKind::Done);
+    }
+
+    #[test]
+    fn custom_statuses_decide_whether_a_task_is_overdue() {
+        let statuses = Statuses::with_extra(&strings(&["archived"]), &strings(&["dropped"]));
+        let overdue = |status: &str| {
+            let mut task = parse_task_str(
+                &format!("---\nstatus: {}\ndue: 2000-01-01\n---\n", status),
+                "task",
+            )
+            .unwrap();
+            task.classify(&statuses);
+            let now = chrono::NaiveDate::from_ymd_opt(2000, 1, 2)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            (task.is_overdue(now), task.is_done(), task.is_cancelled())
+        };
+        assert_eq!(overdue("open"), (true, false, false));
+        assert_eq!(overdue("archived"), (false, true, false));
+        assert_eq!(overdue("dropped"), (false, false, true));
+        assert_eq!(overdue("canceled"), (false, false, true));
+    }
+
+    #[test]
+    fn near_misses_of_known_statuses() {
+        let statuses = Statuses::default();
+        assert_eq!(statuses.near_miss("Dnoe"), None);
+        assert_eq!(statuses.near_miss("doen"), None);
+        assert_eq!(statuses.near_miss("don"), Some("done"));
+        assert_eq!(statuses.near_miss("complted"), Some("completed"));
+        assert_eq!(statuses.near_miss("done"), None);
+        assert_eq!(statuses.near_miss("open"), None);
+    }
+
+    #[test]
+    fn every_synonym_normalizes_to_its_canonical_status() {
+        let statuses = Statuses::default();
+        for (raw, canonical_status) in [
+            ("x", "done"),
+            ("Done", "done"),
+            ("completed", "done"),
+            ("cancelled", "cancelled"),
+            ("Canceled", "cancelled"),
+            ("in-progress", "in-progress"),
+            ("Doing", "in-progress"),
+            ("/", "in-progress"),
+            ("open", "open"),
+            ("todo", "open"),
+        ] {
+            let mut task =
+                parse_task_str(&format!("---\nstatus: \"{}\"\n---\n", raw), "task").unwrap();
+            task.classify(&statuses);
+            task.normalize_status();
+            assert_eq!(task.status, canonical_status, "{}", raw);
+            assert_eq!(canonical(raw, statuses.classify(raw)), canonical_status);
+        }
+    }
+
+    #[test]
+    fn normalizing_keeps_the_custom_classification() {
+        let statuses = Statuses::with_extra(&strings(&["archived"]), &strings(&["dropped"]));
+        let mut task = parse_task_str("---\nstatus: archived\n---\n", "task").unwrap();
+        task.classify(&statuses);
+        task.normalize_status();
+        assert_eq!(task.status, "done");
+        assert!(task.is_done());
+    }
+}
@@ -0,0 +1,660 @@
+//! A small TOML reader producing `serde_json::Value`s, and a writer going the
+//! other way.
+//!
+//! Covers what config files and frontmatter need: tables, arrays of tables,
+//! dotted keys, basic/literal (and multi-line) strings, integers, floats,
+//! booleans, arrays and inline tables. Dates and times are kept as strings so
+//! they go through the same deserializers as YAML values.
+//!
+//! TOML has no null, so the writer leaves out `null` values altogether.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+
+/// Parse a TOML document into a JSON object
+pub fn from_str(input: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|e| anyhow!("TOML error on line {}: {}", parser.line, e))
+}
+
+/// Parse a TOML document straight into a deserializable type
+pub fn from_str_as<T: serde::de::DeserializeOwned>(input: &str) -> Result<T> {
+    Ok(serde_json::from_value(from_str(input)?)?)
+}
+
+/// Write a JSON object as a TOML document. Nested objects become `[tables]`
+/// and arrays of objects become `[[arrays of tables]]`.
+pub fn to_string(value: &Value) -> Result<String> {
+    let Value::Object(table) = value else {
+        bail!("only a table can be written as a TOML document");
+    };
+    let mut out = String::new();
+    write_table(&mut out, &[], table);
+    Ok(out)
+}
+
+/// Whether `value` is written as `[[key]]` sections rather than inline
+fn is_array_of_tables(value: &Value) -> bool {
+    matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object))
+}
+
+fn write_table(out: &mut String, path: &[String], table: &Map<String, Value>) {
+    for (key, value) in table {
+        if value.is_null() || value.is_object() || is_array_of_tables(value) {
+            continue;
+        }
+        out.push_str(&format!("{} = {}\n", write_key(key), inline_value(value)));
+    }
+    for (key, value) in table {
+        let child: Vec<String> = path.iter().cloned().chain([key.clone()]).collect();
+        let header: Vec<String> = child.iter().map(|k| write_key(k)).collect();
+        match value {
+            Value::Object(map) => {
+                // A table holding only sub-tables is implied by their headers
+                let has_values = map
+                    .values()
+                    .any(|v| !v.is_null() && !v.is_object() && !is_array_of_tables(v));
+                if has_values || map.is_empty() {
+                    separate(out);
+                    out.push_str(&format!("[{}]\n", header.join(".")));
+                }
+                write_table(out, &child, map);
+            }
+            Value::Array(items) if is_array_of_tables(value) => {
+                for item in items {
+                    separate(out);
+                    out.push_str(&format!("[[{}]]\n", header.join(".")));
+                    if let Value::Object(map) = item {
+                        write_table(out, &child, map);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Start a new section with a blank line, unless it opens the document
+fn separate(out: &mut String) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+}
+
+fn write_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+fn inline_value(value: &Value) -> String {
+    match value {
+        // JSON string escapes are all valid in TOML basic strings
+        Value::String(_) | Value::Number(_) | Value::Bool(_) => value.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .filter(|v| !v.is_null())
+                .map(inline_value)
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| format!("{} = {}", write_key(k), inline_value(v)))
+                .collect();
+            if entries.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+        Value::Null => unreachable!("nulls are skipped before they are written"),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(found) if found == c => {
+                self.bump();
+                Ok(())
+            }
+            Some(found) => bail!("expected `{}`, found `{}`", c, found),
+            None => bail!("expected `{}`, found end of input", c),
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    /// Skip spaces and tabs on the current line
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => bail!("unexpected `{}` after value", c),
+        }
+    }
+
+    fn document(&mut self) -> Result<Value> {
+        let mut root = Map::new();
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.bump();
+                    let array = self.eat('[');
+                    self.skip_spaces();
+                    let path = self.key_path()?;
+                    self.skip_spaces();
+                    self.expect(']')?;
+                    if array {
+                        self.expect(']')?;
+                        let (last, parents) = path.split_last().expect("key path is never empty");
+                        let parent = table_at(&mut root, parents)?;
+                        match parent
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()))
+                        {
+                            Value::Array(items) => items.push(Value::Object(Map::new())),
+                            _ => bail!("`{}` is already defined as a non-array", last),
+                        }
+                    } else {
+                        table_at(&mut root, &path)?;
+                    }
+                    current = path;
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let path = self.key_path()?;
+                    self.skip_spaces();
+                    self.expect('=')?;
+                    self.skip_spaces();
+                    let value = self.value()?;
+                    let table = table_at(&mut root, &current)?;
+                    insert(table, &path, value)?;
+                    self.end_of_line()?;
+                }
+            }
+        }
+
+        Ok(Value::Object(root))
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>> {
+        let mut path = vec![self.key()?];
+        loop {
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(path);
+            }
+            self.skip_spaces();
+            path.push(self.key()?);
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        match self.peek() {
+            Some('"') => {
+                self.bump();
+                self.basic_string()
+            }
+            Some('\'') => {
+                self.bump();
+                self.literal_string()
+            }
+            _ => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.bump();
+                }
+                if start == self.pos {
+                    match self.peek() {
+                        Some(c) => bail!("expected a key, found `{}`", c),
+                        None => bail!("expected a key, found end of input"),
+                    }
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => {
+                self.pos += 3;
+                self.multiline_string(true).map(Value::String)
+            }
+            Some('\'') if self.starts_with("'''") => {
+                self.pos += 3;
+                self.multiline_string(false).map(Value::String)
+            }
+            Some('"') => {
+                self.bump();
+                self.basic_string().map(Value::String)
+            }
+            Some('\'') => {
+                self.bump();
+                self.literal_string().map(Value::String)
+            }
+            Some('[') => {
+                self.bump();
+                self.array()
+            }
+            Some('{') => {
+                self.bump();
+                self.inline_table()
+            }
+            Some(_) => self.bare_value(),
+            None => bail!("expected a value, found end of input"),
+        }
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let c = self.bump().ok_or_else(|| anyhow!("unterminated escape"))?;
+        Ok(match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '"' => '"',
+            '\\' => '\\',
+            'u' | 'U' => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let hex: String = (0..len).filter_map(|_| self.bump()).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| anyhow!("invalid unicode escape `\\{}{}`", c, hex))?
+            }
+            other => bail!("invalid escape `\\{}`", other),
+        })
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => bail!("unterminated string"),
+                Some('"') => return Ok(out),
+                Some('\\') => out.push(self.escape()?),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => bail!("unterminated string"),
+                Some('\'') => return Ok(out),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn multiline_string(&mut self, basic: bool) -> Result<String> {
+        let delimiter = if basic { "\"\"\"" } else { "'''" };
+        // A newline straight after the opening delimiter is trimmed
+        self.eat('\r');
+        self.eat('\n');
+        let mut out = String::new();
+        loop {
+            if self.starts_with(delimiter) {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.bump() {
+                None => bail!("unterminated multi-line string"),
+                Some('\\') if basic => {
+                    if matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) {
+                        // Line-ending backslash: drop the newline and leading whitespace
+                        while matches!(self.peek(), Some('\n' | '\r' | ' ' | '\t')) {
+                            self.bump();
+                        }
+                    } else {
+                        out.push(self.escape()?);
+                    }
+                }
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            if !self.eat(',') {
+                self.skip_blank();
+                self.expect(']')?;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        let mut table = Map::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(table));
+        }
+        loop {
+            self.skip_spaces();
+            let path = self.key_path()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            insert(&mut table, &path, value)?;
+            self.skip_spaces();
+            if self.eat('}') {
+                return Ok(Value::Object(table));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Numbers, booleans and dates/times (which are returned as strings)
+    fn bare_value(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '}' | '#'))
+        {
+            self.bump();
+        }
+        let mut token: String = self.chars[start..self.pos].iter().collect();
+
+        // Local date-times may use a space instead of `T` between date and time
+        if token.len() == 10
+            && token.as_bytes()[4] == b'-'
+            && self.peek() == Some(' ')
+            && self.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+        {
+            self.bump();
+            let time_start = self.pos;
+            while matches!(self.peek(), Some(c) if !matches!(c, ' ' | '\t' | '\r' | '\n' | ',' | ']' | '}' | '#'))
+            {
+                self.bump();
+            }
+            let time: String = self.chars[time_start..self.pos].iter().collect();
+            token = format!("{}T{}", token, time);
+        }
+
+        match token.as_str() {
+            "" => bail!("expected a value"),
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+
+        let bytes = token.as_bytes();
+        let is_date = bytes.len() >= 10 && bytes[4] == b'-' && bytes[7] == b'-';
+        let is_time = bytes.len() >= 5 && bytes[2] == b':';
+        if is_date || is_time {
+            return Ok(Value::String(token));
+        }
+
+        let digits = token.replace('_', "");
+        if let Ok(n) = digits.parse::<i64>() {
+            return Ok(Value::from(n));
+        }
+        if let Some(n) = digits
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            return Ok(Value::Number(n));
+        }
+        bail!("invalid value `{}`", token)
+    }
+}
+
+/// Walk (creating as needed) to the table at `path`. Arrays of tables resolve
+/// to their most recently added element, as TOML headers do.
+fn table_at<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+) -> Result<&'a mut Map<String, Value>> {
+    let mut table = root;
+    for key in path {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = match entry {
+            Value::Object(map) => map,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Object(map)) => map,
+                _ => bail!("`{}` is not a table", key),
+            },
+            _ => bail!("`{}` is not a table", key),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Map<String, Value>, path: &[String], value: Value) -> Result<()> {
+    let (last, parents) = path.split_last().expect("key path is never empty");
+    let table = table_at(table, parents)?;
+    if table.contains_key(last) {
+        bail!("duplicate key `{}`", last);
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(input: &str) -> Value {
+        from_str(input).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        from_str(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn strings_and_their_escapes() {
+        let doc = parse(concat!(
+            "basic = \"tab\\tquote\\\" \\u00e9 \\U0001F600\"\n",
+            "literal = 'C:\\path\\n'\n",
+            "multi = \"\"\"\nline one \\\n    continued\"\"\"\n",
+            "raw = '''\nkeep \\n\n'''\n",
+        ));
+        assert_eq!(
+            doc,
+            json!({
+                "basic": "tab\tquote\" \u{e9} \u{1F600}",
+                "literal": "C:\\path\\n",
+                "multi": "line one continued",
+                "raw": "keep \\n\n",
+            })
+        );
+    }
+
+    #[test]
+    fn numbers_booleans_and_arrays() {
+        let doc = parse("n = 1_000\nx = -2.5\nyes = true\nlist = [\n  1, \"two\",\n  [3],\n]\n");
+        assert_eq!(
+            doc,
+            json!({"n": 1000, "x": -2.5, "yes": true, "list": [1, "two", [3]]})
+        );
+        assert_eq!(parse("empty = []"), json!({"empty": []}));
+    }
+
+    #[test]
+    fn dates_and_times_stay_strings() {
+        let doc = parse(concat!(
+            "day = 2026-01-02\n",
+            "local = 2026-01-02T09:30:00\n",
+            "spaced = 2026-01-02 09:30\n",
+            "offset = 2026-01-02T09:30:00+01:00\n",
+            "time = 09:30:00\n",
+        ));
+        assert_eq!(
+            doc,
+            json!({
+                "day": "2026-01-02",
+                "local": "2026-01-02T09:30:00",
+                "spaced": "2026-01-02T09:30",
+                "offset": "2026-01-02T09:30:00+01:00",
+                "time": "09:30:00",
+            })
+        );
+    }
+
+    #[test]
+    fn tables_inline_tables_and_dotted_keys() {
+        let doc = parse(concat!(
+            "# a comment\n",
+            "a.b = 1 # trailing comment\n",
+            "point = { x = 1, y.z = 2 }\n",
+            "[server]\n",
+            "\"quoted key\" = 'v'\n",
+            "[server.limits]\n",
+            "max = 3\n",
+            "[[tier]]\n",
+            "class = \"ok\"\n",
+            "[[tier]]\n",
+            "class = \"crit\"\n",
+        ));
+        assert_eq!(
+            doc,
+            json!({
+                "a": {"b": 1},
+                "point": {"x": 1, "y": {"z": 2}},
+                "server": {"quoted key": "v", "limits": {"max": 3}},
+                "tier": [{"class": "ok"}, {"class": "crit"}],
+            })
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            error("a = 1\na = 2"),
+            "TOML error on line 2: duplicate key `a`"
+        );
+        assert!(error("a = \"open").contains("unterminated string"));
+        assert!(error("a = \"\\q\"").contains("invalid escape"));
+        assert!(error("a = nope").contains("invalid value `nope`"));
+        assert!(error("a = [1 2]").starts_with("TOML error on line 1"));
+        assert!(error("a =").contains("expected a value"));
+        assert!(error("= 1").contains("expected a key"));
+        assert!(error("a = 1\n[a]").contains("`a` is not a table"));
+        assert!(error("a = 1 b = 2").starts_with("TOML error on line 1"));
+    }
+
+    #[test]
+    fn written_documents_read_back() {
+        let value = json!({
+            "title": "Say \"hi\"",
+            "skip": null,
+            "tags": ["a", "b"],
+            "nested": {"n": 1},
+            "tier": [{"class": "ok"}, {"class": "crit"}],
+        });
+        let text = to_string(&value).unwrap();
+        assert!(!text.contains("skip"));
+        let mut expected = value;
+        expected.as_object_mut().unwrap().remove("skip");
+        assert_eq!(parse(&text), expected);
+    }
+}
@@ -0,0 +1,594 @@
+//! IANA timezones read from the system tz database (`/usr/share/zoneinfo`).
+//!
+//! Only UTC offsets are needed to work out the local date and time, so this
+//! reads the transition table of a TZif file and, for instants after the last
+//! recorded transition, the POSIX TZ rule in the version 2+ footer. Slim
+//! zoneinfo builds rely on that rule for every daylight saving change after
+//! the current year or so.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, TimeDelta, Weekday};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A named timezone and its UTC offset history
+#[derive(Debug, Clone)]
+pub struct Tz {
+    name: String,
+    /// Transition instants (Unix seconds), ascending
+    transitions: Vec<i64>,
+    /// Offset in seconds taking effect at each transition
+    offsets: Vec<i32>,
+    /// Offset before the first transition
+    initial_offset: i32,
+    /// Rule for instants after the last transition, from the footer
+    rule: Option<PosixTz>,
+}
+
+impl Tz {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Offset from UTC, in seconds, at the Unix instant `timestamp`
+    pub fn offset_at(&self, timestamp: i64) -> i32 {
+        let after_last = self
+            .transitions
+            .last()
+            .is_none_or(|&last| timestamp >= last);
+        if let Some(rule) = self.rule.as_ref().filter(|_| after_last) {
+            return rule.offset_at(timestamp);
+        }
+        match self.transitions.partition_point(|&t| t <= timestamp) {
+            0 => self.initial_offset,
+            i => self.offsets[i - 1],
+        }
+    }
+
+    /// Wall-clock time in this zone for a UTC date-time
+    pub fn from_utc(&self, utc: NaiveDateTime) -> NaiveDateTime {
+        let offset = self.offset_at(utc.and_utc().timestamp());
+        utc + TimeDelta::seconds(offset.into())
+    }
+
+    /// Parse a TZif file (version 1, or the 64-bit block of version 2+)
+    fn from_tzif(name: &str, data: &[u8]) -> Result<Tz> {
+        let mut reader = Reader { data, pos: 0 };
+        let header = reader.header()?;
+        let version = header.version;
+        let (header, time_size) = if version >= b'2' {
+            reader.skip(header.data_len(4))?;
+            (reader.header()?, 8)
+        } else {
+            (header, 4)
+        };
+        let data_end = reader.pos + header.data_len(time_size);
+
+        let mut transitions = Vec::with_capacity(header.time_count);
+        for _ in 0..header.time_count {
+            transitions.push(if time_size == 8 {
+                reader.i64()?
+            } else {
+                reader.i32()?.into()
+            });
+        }
+        let indices = reader.take(header.time_count)?.to_vec();
+        let mut types = Vec::with_capacity(header.type_count);
+        for _ in 0..header.type_count {
+            let offset = reader.i32()?;
+            let is_dst = reader.take(1)?[0] != 0;
+            reader.skip(1)?;
+            types.push((offset, is_dst));
+        }
+        if types.is_empty() {
+            bail!("no local time types");
+        }
+
+        let offsets = indices
+            .iter()
+            .map(|&i| types.get(i as usize).map(|t| t.0))
+            .collect::<Option<Vec<_>>>()
+            .context("transition refers to a missing local time type")?;
+        // Per tzfile(5), times before the first transition use the first
+        // standard-time type
+        let initial_offset = types.iter().find(|t| !t.1).unwrap_or(&types[0]).0;
+
+        // The footer is the TZ string between two newlines; it may be empty
+        let rule = match data.get(data_end..) {
+            Some([b'\n', footer @ ..]) if version >= b'2' => {
+                let footer = footer.split(|&b| b == b'\n').next().unwrap_or_default();
+                let footer = std::str::from_utf8(footer).context("footer is not UTF-8")?;
+                if footer.is_empty() {
+                    None
+                } else {
+                    Some(
+                        footer
+                            .parse()
+                            .with_context(|| format!("invalid TZ rule {:?}", footer))?,
+                    )
+                }
+            }
+            _ => None,
+        };
+
+        Ok(Tz {
+            name: name.to_string(),
+            transitions,
+            offsets,
+            initial_offset,
+            rule,
+        })
+    }
+}
+
+/// A POSIX TZ rule such as `EST5EDT,M3.2.0,M11.1.0`: a standard offset and,
+/// optionally, a daylight saving offset with the rules for when it starts and
+/// ends each year
+#[derive(Debug, Clone, PartialEq)]
+struct PosixTz {
+    /// Standard time offset from UTC in seconds (east positive)
+    std_offset: i32,
+    dst: Option<Dst>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Dst {
+    offset: i32,
+    /// When daylight saving starts, in standard local time
+    start: Transition,
+    /// When it ends, in daylight saving local time
+    end: Transition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transition {
+    day: RuleDay,
+    /// Seconds after local midnight; may be negative or past 24 hours
+    time: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleDay {
+    /// `Jn`: day 1 to 365, never counting February 29
+    Julian(u16),
+    /// `n`: day 0 to 365, counting February 29 in leap years
+    ZeroBased(u16),
+    /// `Mm.w.d`: weekday `d` (0 is Sunday) of week `w` (5 is the last) of month `m`
+    MonthWeekDay { month: u32, week: u32, weekday: u32 },
+}
+
+impl PosixTz {
+    fn offset_at(&self, timestamp: i64) -> i32 {
+        let Some(dst) = &self.dst else {
+            return self.std_offset;
+        };
+        let local = timestamp + i64::from(self.std_offset);
+        let Some(year) = chrono::DateTime::from_timestamp(local, 0).map(|dt| dt.year()) else {
+            return self.std_offset;
+        };
+        let (Some(start), Some(end)) = (
+            dst.start.instant(year, self.std_offset),
+            dst.end.instant(year, dst.offset),
+        ) else {
+            return self.std_offset;
+        };
+        let in_dst = if start < end {
+            start <= timestamp && timestamp < end
+        } else {
+            // Southern hemisphere: daylight saving spans the new year
+            !(end <= timestamp && timestamp < start)
+        };
+        if in_dst {
+            dst.offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+impl Transition {
+    /// The Unix instant of this transition in `year`, for a local time at `offset`
+    fn instant(&self, year: i32, offset: i32) -> Option<i64> {
+        let date = self.day.date(year)?;
+        let midnight = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+        Some(midnight + i64::from(self.time) - i64::from(offset))
+    }
+}
+
+impl RuleDay {
+    fn date(self, year: i32) -> Option<NaiveDate> {
+        let jan1 = NaiveDate::from_ymd_opt(year, 1, 1)?;
+        match self {
+            RuleDay::Julian(n) => {
+                let leap_shift = u64::from(jan1.leap_year() && n >= 60);
+                jan1.checked_add_days(Days::new(u64::from(n) - 1 + leap_shift))
+            }
+            RuleDay::ZeroBased(n) => jan1.checked_add_days(Days::new(n.into())),
+            RuleDay::MonthWeekDay {
+                month,
+                week,
+                weekday,
+            } => {
+                let weekday = Weekday::try_from(((weekday + 6) % 7) as u8).ok()?;
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let mut date = first.with_day(1 + weekday.days_since(first.weekday()))?;
+                for _ in 1..week {
+                    match date.checked_add_days(Days::new(7)) {
+                        Some(next) if next.month() == month => date = next,
+                        _ => break,
+                    }
+                }
+                Some(date)
+            }
+        }
+    }
+}
+
+impl FromStr for PosixTz {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<PosixTz> {
+        let mut p = RuleParser {
+            s: s.as_bytes(),
+            pos: 0,
+        };
+        p.name()?;
+        let std_offset = -p.offset()?;
+        let dst = if p.at_end() || p.peek() == Some(b',') {
+            None
+        } else {
+            p.name()?;
+            let offset = match p.peek() {
+                Some(b',') | None => std_offset + 3600,
+                Some(_) => -p.offset()?,
+            };
+            // US rules were the default before TZif files carried them
+            let (start, end) = if p.eat(b',') {
+                let start = p.transition()?;
+                p.expect(b',')?;
+                (start, p.transition()?)
+            } else {
+                let us = |month, week| Transition {
+                    day: RuleDay::MonthWeekDay {
+                        month,
+                        week,
+                        weekday: 0,
+                    },
+                    time: 7200,
+                };
+                (us(3, 2), us(11, 1))
+            };
+            Some(Dst { offset, start, end })
+        };
+        if !p.at_end() {
+            bail!("unexpected text at position {}", p.pos);
+        }
+        Ok(PosixTz { std_offset, dst })
+    }
+}
+
+struct RuleParser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl RuleParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.s.len()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<()> {
+        if self.eat(b) {
+            Ok(())
+        } else {
+            Err(anyhow!("expected `{}` at position {}", b as char, self.pos))
+        }
+    }
+
+    /// A zone abbreviation: letters, or anything between `<` and `>`
+    fn name(&mut self) -> Result<()> {
+        let start = self.pos;
+        if self.eat(b'<') {
+            while !self.eat(b'>') {
+                if self.at_end() {
+                    bail!("unclosed `<` in zone name");
+                }
+                self.pos += 1;
+            }
+        } else {
+            while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+        }
+        if self.pos - start < 3 {
+            bail!("zone name too short at position {}", start);
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<i32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.s[start..self.pos])?
+            .parse()
+            .map_err(|_| anyhow!("expected a number at position {}", start))
+    }
+
+    /// `[+-]hh[:mm[:ss]]` in seconds, as written (west of UTC is positive)
+    fn offset(&mut self) -> Result<i32> {
+        let sign = if self.eat(b'-') {
+            -1
+        } else {
+            self.eat(b'+');
+            1
+        };
+        let mut seconds = self.number()? * 3600;
+        for unit in [60, 1] {
+            if !self.eat(b':') {
+                break;
+            }
+            seconds += self.number()? * unit;
+        }
+        Ok(sign * seconds)
+    }
+
+    /// `date[/time]`, the time defaulting to 02:00
+    fn transition(&mut self) -> Result<Transition> {
+        let day = if self.eat(b'J') {
+            RuleDay::Julian(self.number()?.clamp(1, 365) as u16)
+        } else if self.eat(b'M') {
+            let month = self.number()? as u32;
+            self.expect(b'.')?;
+            let week = self.number()? as u32;
+            self.expect(b'.')?;
+            let weekday = self.number()? as u32;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                bail!("invalid M rule M{}.{}.{}", month, week, weekday);
+            }
+            RuleDay::MonthWeekDay {
+                month,
+                week,
+                weekday,
+            }
+        } else {
+            RuleDay::ZeroBased(self.number()?.clamp(0, 365) as u16)
+        };
+        let time = if self.eat(b'/') { self.offset()? } else { 7200 };
+        Ok(Transition { day, time })
+    }
+}
+
+impl fmt::Display for Tz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl FromStr for Tz {
+    type Err = anyhow::Error;
+
+    /// Look up an IANA name such as `Europe/Berlin` in `$TZDIR`, or
+    /// `/usr/share/zoneinfo` when unset
+    fn from_str(name: &str) -> Result<Tz> {
+        let name = name.trim();
+        if name.is_empty()
+            || name.starts_with('/')
+            || name.split('/').any(|part| part == ".." || part.is_empty())
+        {
+            bail!("Invalid timezone name: {:?}", name);
+        }
+        let dir = env::var_os("TZDIR")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/zoneinfo"));
+        let path = dir.join(name);
+        let data = fs::read(&path).with_context(|| format!("Unknown timezone {:?}", name))?;
+        Tz::from_tzif(name, &data)
+            .with_context(|| format!("Failed to read timezone data: {}", path.display()))
+    }
+}
+
+struct Header {
+    version: u8,
+    utc_count: usize,
+    std_count: usize,
+    leap_count: usize,
+    time_count: usize,
+    type_count: usize,
+    char_count: usize,
+}
+
+impl Header {
+    /// Length of the data block following this header
+    fn data_len(&self, time_size: usize) -> usize {
+        self.time_count * (time_size + 1)
+            + self.type_count * 6
+            + self.char_count
+            + self.leap_count * (time_size + 4)
+            + self.std_count
+            + self.utc_count
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .context("truncated TZif data")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?) as usize)
+    }
+
+    fn header(&mut self) -> Result<Header> {
+        if self.take(4)? != b"TZif" {
+            bail!("not a TZif file");
+        }
+        let version = self.take(1)?[0];
+        self.skip(15)?;
+        Ok(Header {
+            version,
+            utc_count: self.count()?,
+            std_count: self.count()?,
+            leap_count: self.count()?,
+            time_count: self.count()?,
+            type_count: self.count()?,
+            char_count: self.count()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PosixTz, RuleDay, Tz};
+    use chrono::NaiveDate;
+
+    /// A version 2 TZif file: an empty version 1 block, then the 64-bit block
+    /// with `transitions` (instant, type index), `types` (offset, is DST) and
+    /// `footer`, as slim zoneinfo builds write them
+    fn tzif(transitions: &[(i64, u8)], types: &[(i32, bool)], footer: &str) -> Vec<u8> {
+        fn header(out: &mut Vec<u8>, time_count: usize, type_count: usize) {
+            out.extend(b"TZif2");
+            out.extend([0; 15]);
+            for count in [0, 0, 0, time_count, type_count, 4] {
+                out.extend((count as u32).to_be_bytes());
+            }
+        }
+        let mut out = Vec::new();
+        header(&mut out, 0, 1);
+        out.extend([0; 6]);
+        out.extend(b"UTC\0");
+
+        header(&mut out, transitions.len(), types.len());
+        for &(at, _) in transitions {
+            out.extend(at.to_be_bytes());
+        }
+        out.extend(transitions.iter().map(|&(_, i)| i));
+        for &(offset, is_dst) in types {
+            out.extend(offset.to_be_bytes());
+            out.extend([u8::from(is_dst), 0]);
+        }
+        out.extend(b"XXX\0");
+        out.extend(format!("\n{}\n", footer).bytes());
+        out
+    }
+
+    fn timestamp(s: &str) -> i64 {
+        s.parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap()
+            .timestamp()
+    }
+
+    fn hours(tz: &Tz, at: &str) -> f64 {
+        f64::from(tz.offset_at(timestamp(at))) / 3600.0
+    }
+
+    #[test]
+    fn slim_files_use_the_footer_rule() {
+        let data = tzif(&[], &[(-18000, false)], "EST5EDT,M3.2.0,M11.1.0");
+        let tz = Tz::from_tzif("America/New_York", &data).unwrap();
+        assert_eq!(hours(&tz, "2026-01-15T12:00:00Z"), -5.0);
+        assert_eq!(hours(&tz, "2026-07-15T12:00:00Z"), -4.0);
+        // 2am EST on the second Sunday of March, and 2am EDT on the first of November
+        assert_eq!(hours(&tz, "2026-03-08T06:59:59Z"), -5.0);
+        assert_eq!(hours(&tz, "2026-03-08T07:00:00Z"), -4.0);
+        assert_eq!(hours(&tz, "2026-11-01T05:59:59Z"), -4.0);
+        assert_eq!(hours(&tz, "2026-11-01T06:00:00Z"), -5.0);
+
+        let utc = NaiveDate::from_ymd_opt(2026, 1, 15)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(tz.from_utc(utc).to_string(), "2026-01-15 07:00:00");
+    }
+
+    #[test]
+    fn the_transition_table_applies_up_to_its_last_entry() {
+        // LMT until 1883, then the rule
+        let data = tzif(
+            &[(timestamp("1883-11-18T17:00:00Z"), 1)],
+            &[(-17762, false), (-18000, false)],
+            "EST5EDT,M3.2.0,M11.1.0",
+        );
+        let tz = Tz::from_tzif("America/New_York", &data).unwrap();
+        assert_eq!(tz.offset_at(timestamp("1880-06-01T00:00:00Z")), -17762);
+        assert_eq!(hours(&tz, "2030-07-01T00:00:00Z"), -4.0);
+    }
+
+    #[test]
+    fn southern_hemisphere_rules_span_the_new_year() {
+        let data = tzif(&[], &[(36000, false)], "AEST-10AEDT,M10.1.0,M4.1.0/3");
+        let tz = Tz::from_tzif("Australia/Sydney", &data).unwrap();
+        assert_eq!(hours(&tz, "2026-01-15T00:00:00Z"), 11.0);
+        assert_eq!(hours(&tz, "2026-07-15T00:00:00Z"), 10.0);
+        assert_eq!(hours(&tz, "2026-12-15T00:00:00Z"), 11.0);
+    }
+
+    #[test]
+    fn rules_without_daylight_saving() {
+        let rule: PosixTz = "<+0530>-5:30".parse().unwrap();
+        assert_eq!(rule.offset_at(0), 19800);
+        let rule: PosixTz = "UTC0".parse().unwrap();
+        assert_eq!(rule.offset_at(0), 0);
+        assert!("X5".parse::<PosixTz>().is_err());
+        assert!("EST5EDT,M13.1.0,M11.1.0".parse::<PosixTz>().is_err());
+    }
+
+    #[test]
+    fn rule_days() {
+        let day = |rule: RuleDay, year| rule.date(year).unwrap().to_string();
+        let last_sunday_of_october = RuleDay::MonthWeekDay {
+            month: 10,
+            week: 5,
+            weekday: 0,
+        };
+        assert_eq!(day(last_sunday_of_october, 2026), "2026-10-25");
+        assert_eq!(day(RuleDay::Julian(60), 2024), "2024-03-01");
+        assert_eq!(day(RuleDay::Julian(60), 2026), "2026-03-01");
+        assert_eq!(day(RuleDay::ZeroBased(59), 2024), "2024-02-29");
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_tzif() {
+        assert!(Tz::from_tzif("x", b"not a zone file").is_err());
+    }
+}
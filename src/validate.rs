@@ -0,0 +1,357 @@
+//! Linting of task frontmatter, reporting problems instead of skipping notes.
+
+use crate::{
+    dependency_name, scan_roots_all, split_frontmatter, task_filename, task_files, toml,
+    FrontmatterFormat, ScanOptions, Statuses, Task, TaskDate,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    Unreadable,
+    NoFrontmatter,
+    InvalidFrontmatter,
+    MissingStatus,
+    InvalidDate,
+    DoneWithoutCompletedDate,
+    Overdue,
+    DuplicateFilename,
+    DanglingDependency,
+}
+
+impl IssueKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            IssueKind::DoneWithoutCompletedDate
+            | IssueKind::Overdue
+            | IssueKind::DuplicateFilename
+            | IssueKind::DanglingDependency => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A single problem found in a task note
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub path: PathBuf,
+    pub kind: IssueKind,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(path: &Path, kind: IssueKind, message: impl Into<String>) -> Issue {
+        Issue {
+            path: path.to_path_buf(),
+            kind,
+            severity: kind.severity(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Date fields checked for parseability, with the parser each one uses
+const DATE_FIELDS: [(&str, bool); 6] = [
+    ("due", true),
+    ("scheduled", true),
+    ("dateCreated", true),
+    ("completedDate", false),
+    ("start", false),
+    ("startDate", false),
+];
+
+/// Lint a single note, returning its issues and the parsed task if it parsed.
+/// Tasks due before `now` are reported as overdue.
+pub fn validate_file(
+    path: &Path,
+    statuses: &Statuses,
+    now: NaiveDateTime,
+) -> (Vec<Issue>, Option<Task>) {
+    let mut issues = Vec::new();
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            issues.push(Issue::new(path, IssueKind::Unreadable, e.to_string()));
+            return (issues, None);
+        }
+    };
+    let Some((frontmatter, _)) = split_frontmatter(&content) else {
+        issues.push(Issue::new(
+            path,
+            IssueKind::NoFrontmatter,
+            "No frontmatter found",
+        ));
+        return (issues, None);
+    };
+
+    // Look at the raw values first so problems can be pinned to a field
+    let raw: Option<serde_yaml::Value> = match frontmatter.format {
+        FrontmatterFormat::Yaml => serde_yaml::from_str(&frontmatter.text).ok(),
+        FrontmatterFormat::Toml => toml::from_str(&frontmatter.text)
+            .ok()
+            .and_then(|value| serde_yaml::to_value(value).ok()),
+    };
+    if let Some(serde_yaml::Value::Mapping(map)) = raw {
+        match map.get("status") {
+            None | Some(serde_yaml::Value::Null) => issues.push(Issue::new(
+                path,
+                IssueKind::MissingStatus,
+                "`status` is missing",
+            )),
+            _ => {}
+        }
+        for (field, allows_time) in DATE_FIELDS {
+            let Some(serde_yaml::Value::String(value)) = map.get(field) else {
+                continue;
+            };
+            // Empty values are read as unset
+            if value.trim().is_empty() {
+                continue;
+            }
+            let valid = if allows_time {
+                value.parse::<TaskDate>().is_ok()
+            } else {
+                value.trim().parse::<NaiveDate>().is_ok()
+            };
+            if !valid {
+                issues.push(Issue::new(
+                    path,
+                    IssueKind::InvalidDate,
+                    format!("`{}` is not a valid date: {}", field, value),
+                ));
+            }
+        }
+    }
+
+    match frontmatter.parse() {
+        Ok(mut task) => {
+            task.filename = task_filename(path);
+            task.path = path.to_path_buf();
+            task.classify(statuses);
+            if task.is_done() && task.completed_date.is_none() {
+                issues.push(Issue::new(
+                    path,
+                    IssueKind::DoneWithoutCompletedDate,
+                    "Task is done but has no `completedDate`",
+                ));
+            }
+            if task.is_overdue(now) {
+                let due = task.due.map(|d| d.to_string()).unwrap_or_default();
+                issues.push(Issue::new(
+                    path,
+                    IssueKind::Overdue,
+                    format!("Overdue since {}", due),
+                ));
+            }
+            (issues, Some(task))
+        }
+        Err(e) => {
+            // Field-level issues already explain the failure; only add a
+            // generic one when nothing more specific was found
+            if issues.is_empty() {
+                issues.push(Issue::new(
+                    path,
+                    IssueKind::InvalidFrontmatter,
+                    format!("{:#}", e),
+                ));
+            }
+            (issues, None)
+        }
+    }
+}
+
+/// Lint every note that a scan of `vault_paths` would read, as of `now`
+pub fn validate_vault(
+    vault_paths: &[PathBuf],
+    options: &ScanOptions,
+    now: NaiveDateTime,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut by_filename: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    let mut dependencies: Vec<(PathBuf, Vec<String>)> = Vec::new();
+
+    for root in scan_roots_all(vault_paths, options) {
+        for path in task_files(&root, options) {
+            let (file_issues, task) = validate_file(&path, &options.statuses, now);
+            issues.extend(file_issues);
+            if let Some(task) = task {
+                if !task.depends_on.is_empty() {
+                    dependencies.push((path.clone(), task.depends_on));
+                }
+                by_filename.entry(task.filename).or_default().push(path);
+            }
+        }
+    }
+
+    for (filename, paths) in by_filename.iter().filter(|(_, paths)| paths.len() > 1) {
+        for path in paths {
+            let others: Vec<String> = paths
+                .iter()
+                .filter(|p| *p != path)
+                .map(|p| p.display().to_string())
+                .collect();
+            issues.push(Issue::new(
+                path,
+                IssueKind::DuplicateFilename,
+                format!("`{}` also exists at {}", filename, others.join(", ")),
+            ));
+        }
+    }
+
+    for (path, depends_on) in &dependencies {
+        for dep in depends_on {
+            if !by_filename.contains_key(dependency_name(dep)) {
+                issues.push(Issue::new(
+                    path,
+                    IssueKind::DanglingDependency,
+                    format!("`dependsOn` entry `{}` matches no task", dep),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+/// One `path: severity kind: message` line per issue
+pub fn render_issues_plain(issues: &[Issue]) -> String {
+    issues
+        .iter()
+        .map(|issue| {
+            let severity = match issue.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            let kind = serde_json::to_value(issue.kind)
+                .ok()
+                .and_then(|v| v.as_str().map(String::from))
+                .unwrap_or_default();
+            format!(
+                "{}: {} [{}] {}",
+                issue.path.display(),
+                severity,
+                kind,
+                issue.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 1, 15)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    /// The kinds of issue found in a note with this content
+    fn kinds(content: &str) -> Vec<IssueKind> {
+        let dir = crate::testutil::TempDir::new();
+        let path = dir.write("task.md", content);
+        let (issues, _) = validate_file(&path, &Statuses::default(), now());
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn a_clean_note_has_no_issues() {
+        assert!(kinds("---\nstatus: open\ndue: 2026-02-01\ntags: [a]\n---\n").is_empty());
+        assert!(kinds("---\nstatus: done\ncompletedDate: 2026-01-02\n---\n").is_empty());
+    }
+
+    #[test]
+    fn a_missing_status_is_an_error() {
+        assert_eq!(kinds("---\ntitle: x\n---\n"), [IssueKind::MissingStatus]);
+        assert_eq!(kinds("---\nstatus:\n---\n"), [IssueKind::MissingStatus]);
+        assert_eq!(kinds("no frontmatter"), [IssueKind::NoFrontmatter]);
+        assert_eq!(IssueKind::MissingStatus.severity(), Severity::Error);
+    }
+
+    #[test]
+    fn bad_dates_name_the_field() {
+        let dir = crate::testutil::TempDir::new();
+        let path = dir.write(
+            "task.md",
+            "---\nstatus: open\ndue: tomorrow\ncompletedDate: 2026-01-02T09:00\nstart: \"\"\n---\n",
+        );
+        let (issues, task) = validate_file(&path, &Statuses::default(), now());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "`due` is not a valid date: tomorrow",
+                "`completedDate` is not a valid date: 2026-01-02T09:00",
+            ]
+        );
+        assert!(issues.iter().all(|i| i.kind == IssueKind::InvalidDate));
+        assert!(task.is_none());
+    }
+
+    #[test]
+    fn tags_and_projects_of_the_wrong_type_are_invalid() {
+        assert_eq!(
+            kinds("---\nstatus: open\ntags:\n  work: true\n---\n"),
+            [IssueKind::InvalidFrontmatter]
+        );
+        assert_eq!(
+            kinds("---\nstatus: open\nprojects: [[a, b]]\n---\n"),
+            [IssueKind::InvalidFrontmatter]
+        );
+    }
+
+    #[test]
+    fn toml_frontmatter_is_linted_the_same_way() {
+        assert!(kinds("+++\nstatus = \"open\"\ndue = 2026-02-01\n+++\n").is_empty());
+        assert_eq!(
+            kinds("+++\ntitle = \"x\"\n+++\n"),
+            [IssueKind::MissingStatus]
+        );
+        assert_eq!(
+            kinds("+++\nstatus = \"open\"\ndue = \"soon\"\n+++\n"),
+            [IssueKind::InvalidDate]
+        );
+        assert_eq!(
+            kinds("+++\nstatus = \"open\"\ndue = 2026-01-01\n+++\n"),
+            [IssueKind::Overdue]
+        );
+        assert_eq!(
+            kinds("+++\nstatus = \"open\"\ntags = { a = 1 }\n+++\n"),
+            [IssueKind::InvalidFrontmatter]
+        );
+        assert_eq!(
+            kinds("+++\nstatus = \"open\n+++\n"),
+            [IssueKind::InvalidFrontmatter]
+        );
+    }
+
+    #[test]
+    fn overdue_and_done_without_a_date_are_warnings() {
+        assert_eq!(
+            kinds("---\nstatus: open\ndue: 2026-01-14\n---\n"),
+            [IssueKind::Overdue]
+        );
+        assert_eq!(
+            kinds("---\nstatus: done\n---\n"),
+            [IssueKind::DoneWithoutCompletedDate]
+        );
+        assert_eq!(IssueKind::Overdue.severity(), Severity::Warning);
+    }
+}
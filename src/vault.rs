@@ -0,0 +1,200 @@
+//! Locating the TaskNotes folders of a vault from the plugin's own settings,
+//! and linking back to notes in Obsidian.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the TaskNotes plugin keeps its settings, relative to the vault root
+pub const PLUGIN_SETTINGS: &str = ".obsidian/plugins/tasknotes/data.json";
+
+/// The parts of the TaskNotes settings needed to find the notes
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct PluginSettings {
+    tasks_folder: Option<String>,
+    archive_folder: Option<String>,
+}
+
+/// The folders to scan for a vault
+#[derive(Debug, Clone)]
+pub struct VaultFolders {
+    /// The configured tasks folder, or the vault root itself without settings
+    pub tasks: PathBuf,
+    /// The configured archive folder, when it is set and exists
+    pub archive: Option<PathBuf>,
+    /// Whether the folders came from the plugin settings
+    pub from_settings: bool,
+}
+
+/// Resolve the task folders of the vault at `root` from its TaskNotes
+/// settings. Without a settings file, or one that doesn't name a tasks folder,
+/// the whole vault is scanned; a settings file that can't be parsed is an error.
+pub fn locate(root: &Path) -> Result<VaultFolders> {
+    let whole_vault = VaultFolders {
+        tasks: root.to_path_buf(),
+        archive: None,
+        from_settings: false,
+    };
+    let settings_path = root.join(PLUGIN_SETTINGS);
+    let content = match fs::read_to_string(&settings_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(whole_vault),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", settings_path.display()))
+        }
+    };
+    let settings: PluginSettings = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", settings_path.display()))?;
+
+    // Folders are stored vault-relative with forward slashes
+    let folder = |name: Option<String>| {
+        name.map(|name| name.trim().trim_matches('/').to_string())
+            .filter(|name| !name.is_empty())
+            .map(|name| root.join(name))
+    };
+    let Some(tasks) = folder(settings.tasks_folder) else {
+        return Ok(whole_vault);
+    };
+    Ok(VaultFolders {
+        tasks,
+        archive: folder(settings.archive_folder).filter(|archive| archive.is_dir()),
+        from_settings: true,
+    })
+}
+
+/// The vault a note belongs to: the closest folder above it holding `.obsidian`
+pub fn vault_root_of(note: &Path) -> Option<PathBuf> {
+    let note = fs::canonicalize(note).ok()?;
+    note.ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".obsidian").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// An `obsidian://open` link to the note at `path`. The vault is `vault_name`
+/// or else the name of the folder holding `.obsidian`; the file is the path
+/// within that vault, or just the note name when the vault root isn't known.
+/// `None` when no vault name can be found.
+pub fn obsidian_uri(path: &Path, vault_name: Option<&str>) -> Option<String> {
+    let root = vault_root_of(path);
+    let vault = match vault_name {
+        Some(name) => name.to_string(),
+        None => root.as_deref()?.file_name()?.to_string_lossy().into_owned(),
+    };
+    let file = root
+        .and_then(|root| {
+            let note = fs::canonicalize(path).ok()?;
+            let relative = note.strip_prefix(root).ok()?;
+            let parts: Vec<String> = relative
+                .iter()
+                .map(|c| c.to_string_lossy().into_owned())
+                .collect();
+            Some(parts.join("/"))
+        })
+        .or_else(|| Some(path.file_name()?.to_string_lossy().into_owned()))?;
+    Some(format!(
+        "obsidian://open?vault={}&file={}",
+        percent_encode(&vault),
+        percent_encode(&file)
+    ))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn settings_name_the_tasks_and_archive_folders() {
+        let dir = TempDir::new();
+        dir.write(
+            PLUGIN_SETTINGS,
+            r#"{"tasksFolder": " /Work/Tasks/ ", "archiveFolder": "Work/Archive", "other": 1}"#,
+        );
+        fs::create_dir_all(dir.path().join("Work/Archive")).unwrap();
+        let folders = locate(dir.path()).unwrap();
+        assert_eq!(folders.tasks, dir.path().join("Work/Tasks"));
+        assert_eq!(folders.archive, Some(dir.path().join("Work/Archive")));
+        assert!(folders.from_settings);
+    }
+
+    #[test]
+    fn a_missing_archive_folder_is_left_out() {
+        let dir = TempDir::new();
+        dir.write(
+            PLUGIN_SETTINGS,
+            r#"{"tasksFolder": "Tasks", "archiveFolder": "Gone"}"#,
+        );
+        let folders = locate(dir.path()).unwrap();
+        assert_eq!(folders.tasks, dir.path().join("Tasks"));
+        assert_eq!(folders.archive, None);
+    }
+
+    #[test]
+    fn without_a_tasks_folder_the_whole_vault_is_scanned() {
+        let dir = TempDir::new();
+        let folders = locate(dir.path()).unwrap();
+        assert_eq!(folders.tasks, dir.path());
+        assert!(!folders.from_settings);
+
+        for settings in [r#"{}"#, r#"{"tasksFolder": ""}"#, r#"{"tasksFolder": "/"}"#] {
+            dir.write(PLUGIN_SETTINGS, settings);
+            let folders = locate(dir.path()).unwrap();
+            assert_eq!(folders.tasks, dir.path(), "{}", settings);
+            assert!(!folders.from_settings && folders.archive.is_none());
+        }
+    }
+
+    #[test]
+    fn malformed_settings_are_an_error() {
+        let dir = TempDir::new();
+        dir.write(PLUGIN_SETTINGS, "{\"tasksFolder\": ");
+        let err = locate(dir.path()).unwrap_err().to_string();
+        assert!(err.starts_with("Failed to parse"), "{}", err);
+        dir.write(PLUGIN_SETTINGS, r#"{"tasksFolder": 3}"#);
+        assert!(locate(dir.path()).is_err());
+    }
+
+    #[test]
+    fn only_unreserved_characters_are_left_unencoded() {
+        assert_eq!(percent_encode("Aa0-._~"), "Aa0-._~");
+        assert_eq!(percent_encode("My Vault/a&b=c"), "My%20Vault%2Fa%26b%3Dc");
+        assert_eq!(percent_encode("Café 日"), "Caf%C3%A9%20%E6%97%A5");
+    }
+
+    #[test]
+    fn links_open_the_note_within_its_vault() {
+        let dir = TempDir::new();
+        fs::create_dir_all(dir.path().join("My Vault/.obsidian")).unwrap();
+        let note = dir.write("My Vault/Task Notes/Café.md", "");
+        assert_eq!(
+            obsidian_uri(&note, None).as_deref(),
+            Some("obsidian://open?vault=My%20Vault&file=Task%20Notes%2FCaf%C3%A9.md")
+        );
+        assert_eq!(
+            obsidian_uri(&note, Some("Other")).as_deref(),
+            Some("obsidian://open?vault=Other&file=Task%20Notes%2FCaf%C3%A9.md")
+        );
+
+        let loose = dir.write("loose/note.md", "");
+        assert_eq!(obsidian_uri(&loose, None), None);
+        assert_eq!(
+            obsidian_uri(&loose, Some("V")).as_deref(),
+            Some("obsidian://open?vault=V&file=note.md")
+        );
+    }
+}
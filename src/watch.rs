@@ -0,0 +1,67 @@
+//! Waiting for task notes to change, for `--watch`.
+//!
+//! Changes are found by polling file modification times and sizes, which
+//! works the same on every platform and filesystem (including network mounts
+//! where change notifications are unreliable).
+
+use crate::{scan_roots_all, task_files, ScanOptions};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the vault is checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long the vault must stay unchanged before a change is reported, so a
+/// burst of saves (or a sync tool rewriting many files) triggers one re-run
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Modification time and size of every note a scan of `vault_paths` would read
+pub type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Walk the vault for a [`Snapshot`]. Folders that can't be read are skipped
+/// silently: this runs twice a second, and the re-scan after each change
+/// already warns about them.
+pub fn snapshot(vault_paths: &[PathBuf], options: &ScanOptions) -> Snapshot {
+    let options = ScanOptions {
+        quiet: true,
+        ..options.clone()
+    };
+    scan_roots_all(vault_paths, &options)
+        .iter()
+        .flat_map(|root| task_files(root, &options))
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let size = metadata.map(|m| m.len()).unwrap_or(0);
+            (path, (modified, size))
+        })
+        .collect()
+}
+
+/// Block until a note under `vault_paths` is added, removed or modified
+/// relative to `previous`, then return the new snapshot
+pub fn wait_for_change(
+    vault_paths: &[PathBuf],
+    options: &ScanOptions,
+    previous: &Snapshot,
+) -> Snapshot {
+    let mut current = loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = snapshot(vault_paths, options);
+        if &current != previous {
+            break current;
+        }
+    };
+    // Debounce: keep waiting while files are still changing
+    loop {
+        thread::sleep(DEBOUNCE);
+        let next = snapshot(vault_paths, options);
+        if next == current {
+            return current;
+        }
+        current = next;
+    }
+}
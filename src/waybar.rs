@@ -0,0 +1,159 @@
+//! JSON output for waybar custom modules.
+
+use crate::Task;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// The object waybar expects from a `return-type: json` custom module
+#[derive(Debug, Serialize)]
+pub struct WaybarOutput {
+    pub text: String,
+    pub tooltip: String,
+    pub class: String,
+}
+
+/// The `class` names emitted, so they can match the CSS in use
+#[derive(Debug, Clone)]
+pub struct WaybarClasses {
+    /// Nothing overdue or due today
+    pub normal: String,
+    /// At least one task due today
+    pub warning: String,
+    /// At least one task overdue
+    pub critical: String,
+    /// Classes picked by the count instead; see [`tier_class`]
+    pub tiers: Vec<WaybarTier>,
+}
+
+impl Default for WaybarClasses {
+    fn default() -> WaybarClasses {
+        WaybarClasses {
+            normal: "normal".to_string(),
+            warning: "warning".to_string(),
+            critical: "critical".to_string(),
+            tiers: Vec::new(),
+        }
+    }
+}
+
+/// A class for counts up to `max`, or for any count when `max` is left out
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaybarTier {
+    #[serde(default)]
+    pub max: Option<usize>,
+    pub class: String,
+}
+
+/// The class of the first of `tiers` whose `max` is at least `count`, or
+/// `None` when no tier covers it
+pub fn tier_class(tiers: &[WaybarTier], count: usize) -> Option<&str> {
+    tiers
+        .iter()
+        .find(|tier| tier.max.is_none_or(|max| count <= max))
+        .map(|tier| tier.class.as_str())
+}
+
+impl WaybarOutput {
+    /// Build the module output for `count`, with a tooltip listing the tasks
+    /// overdue and due today as of `now` and a class from the tier matching
+    /// `count`, or else one reflecting the most urgent of them.
+    pub fn new(
+        tasks: &[Task],
+        count: usize,
+        classes: &WaybarClasses,
+        now: NaiveDateTime,
+    ) -> WaybarOutput {
+        let overdue: Vec<&Task> = tasks.iter().filter(|t| t.is_overdue(now)).collect();
+        let due_today: Vec<&Task> = tasks
+            .iter()
+            .filter(|t| t.is_due_today(now.date()) && t.is_open() && !t.is_overdue(now))
+            .collect();
+
+        let mut lines = vec![
+            format!("{} overdue", overdue.len()),
+            format!("{} due today", due_today.len()),
+        ];
+        for (heading, group) in [("Overdue", &overdue), ("Due today", &due_today)] {
+            if !group.is_empty() {
+                lines.push(String::new());
+                lines.push(format!("{} ({}):", heading, group.len()));
+                lines.extend(group.iter().map(|t| format!("  {}", t.display_name())));
+            }
+        }
+
+        let class = if let Some(class) = tier_class(&classes.tiers, count) {
+            class
+        } else if !overdue.is_empty() {
+            &classes.critical
+        } else if !due_today.is_empty() {
+            &classes.warning
+        } else {
+            &classes.normal
+        };
+
+        WaybarOutput {
+            text: count.to_string(),
+            tooltip: lines.join("\n"),
+            class: class.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_task_str;
+
+    fn tier(max: Option<usize>, class: &str) -> WaybarTier {
+        WaybarTier {
+            max,
+            class: class.to_string(),
+        }
+    }
+
+    fn tiers() -> Vec<WaybarTier> {
+        vec![
+            tier(Some(0), "ok"),
+            tier(Some(3), "warn"),
+            tier(None, "crit"),
+        ]
+    }
+
+    #[test]
+    fn counts_pick_the_first_tier_whose_max_covers_them() {
+        let tiers = tiers();
+        assert_eq!(tier_class(&tiers, 0), Some("ok"));
+        assert_eq!(tier_class(&tiers, 1), Some("warn"));
+        assert_eq!(tier_class(&tiers, 3), Some("warn"));
+        assert_eq!(tier_class(&tiers, 4), Some("crit"));
+        assert_eq!(tier_class(&tiers, usize::MAX), Some("crit"));
+    }
+
+    #[test]
+    fn counts_past_the_last_bounded_tier_have_no_class() {
+        let tiers = [tier(Some(0), "ok"), tier(Some(3), "warn")];
+        assert_eq!(tier_class(&tiers, 3), Some("warn"));
+        assert_eq!(tier_class(&tiers, 4), None);
+        assert_eq!(tier_class(&[], 0), None);
+    }
+
+    #[test]
+    fn tiers_win_over_the_urgency_classes() {
+        let overdue = parse_task_str("---\nstatus: open\ndue: 2000-01-01\n---\n", "late").unwrap();
+        let now = chrono::NaiveDate::from_ymd_opt(2000, 1, 2)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut classes = WaybarClasses::default();
+        assert_eq!(WaybarOutput::new(&[], 0, &classes, now).class, "normal");
+        assert_eq!(
+            WaybarOutput::new(std::slice::from_ref(&overdue), 1, &classes, now).class,
+            "critical"
+        );
+        classes.tiers = tiers();
+        assert_eq!(
+            WaybarOutput::new(&[overdue], 1, &classes, now).class,
+            "warn"
+        );
+    }
+}